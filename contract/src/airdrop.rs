@@ -0,0 +1,50 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use crate::*;
+
+/// Length of the sha256 hash used for merkle tree nodes
+pub const MERKLE_NODE_LEN: usize = 32;
+/// Number of claim flags stored in one bitmap word
+pub const CLAIMED_BITMAP_WORD_BITS: u64 = 128;
+/// First byte of the hashed merkle leaf data, so a leaf can't be passed off as an inner node
+pub const MERKLE_LEAF_PREFIX: u8 = 0x00;
+/// First byte of the hashed merkle inner node data
+pub const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Token distribution claimable by accounts listed in a merkle tree
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Airdrop {
+    /// Root of the merkle tree of `(index, amount, account_id)` leaves
+    pub merkle_root: Base64VecU8,
+    /// Amount of tokens reserved for the airdrop
+    pub total: U128,
+    /// Amount of tokens left in the escrow of the airdrop. Escrowed tokens are out of the owner's balance
+    pub remaining: U128,
+    /// Block timestamp (in nanoseconds) after which claims are closed
+    pub expires_at: U64,
+    /// Defines if the owner has reclaimed the unclaimed remainder
+    pub reclaimed: bool,
+}
+
+/// Merkle tree leaf: `sha256(0x00 || index as u64 LE || amount as u128 LE || account_id)`
+pub fn airdrop_leaf(index: u64, amount: Balance, account_id: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + 8 + 16 + account_id.len());
+    data.push(MERKLE_LEAF_PREFIX);
+    data.extend_from_slice(&index.to_le_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(account_id.as_bytes());
+    env::sha256(&data)
+}
+
+/// Merkle tree inner node: `sha256(0x01 || min(a, b) || max(a, b))`
+pub fn merkle_node(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    env::sha256(&[&[MERKLE_NODE_PREFIX], left, right].concat())
+}
+
+/// Verify the merkle proof for the leaf, see `merkle_node`
+pub fn verify_merkle_proof(leaf: Vec<u8>, proof: &[Base64VecU8], root: &[u8]) -> bool {
+    let computed_root = proof.iter().fold(leaf, |node, sibling| merkle_node(&node, &sibling.0));
+    computed_root == root
+}
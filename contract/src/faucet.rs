@@ -0,0 +1,57 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use crate::*;
+
+/// Testnet faucet settings. Only compiled with the `faucet` feature
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FaucetConfig {
+    /// Amount of tokens granted per claim
+    pub amount: U128,
+    /// Minimal time (in nanoseconds) between two claims of the same account
+    pub cooldown: U64,
+}
+
+impl Default for FaucetConfig {
+    /// Faucet is disabled until the owner sets a non-zero amount
+    fn default() -> Self {
+        Self {
+            amount: U128(0),
+            cooldown: U64(0),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grant faucet tokens from the owner's reserve to the caller
+    pub fn faucet_claim(&mut self) {
+        let predecessor_account_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        assert!(self.faucet_config.amount.0 > 0, "Faucet is disabled");
+        if let Some(last_claimed_at) = self.faucet_claims.get(&predecessor_account_id) {
+            let next_claim_at = last_claimed_at.saturating_add(self.faucet_config.cooldown.0);
+            assert!(now >= next_claim_at, "Faucet can be claimed again at {}", next_claim_at);
+        }
+        self.faucet_claims.insert(&predecessor_account_id, &now);
+        self.internal_credit_bonus(&predecessor_account_id, self.faucet_config.amount.0, BonusSource::Reserve);
+        log!(
+            "Account @{} has claimed {} ${} from faucet",
+            predecessor_account_id,
+            self.faucet_config.amount.0,
+            &self.metadata.get().unwrap().symbol,
+        );
+    }
+
+    /// Set the faucet settings
+    pub fn set_faucet_config(&mut self, config: FaucetConfig) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        self.faucet_config = config;
+        log!("Faucet settings have been updated");
+    }
+
+    /// Show the faucet settings
+    pub fn faucet_config(&self) -> FaucetConfig {
+        self.faucet_config.clone()
+    }
+}
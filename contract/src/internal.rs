@@ -1,6 +1,177 @@
 use crate::*;
 
-#[near_bindgen]
 impl Contract {
+    /// Register the account in the FT ledger unless it's already registered
+    pub(crate) fn internal_register_account_if_needed(&mut self, account_id: &AccountId) {
+        if self.token.accounts.get(account_id).is_none() {
+            self.token.internal_register_account(account_id);
+        }
+    }
 
+    /// Credit bonus tokens to the account from the given source
+    pub(crate) fn internal_credit_bonus(&mut self, account_id: &AccountId, amount: Balance, source: BonusSource) {
+        if amount == 0 {
+            return;
+        }
+        self.internal_register_account_if_needed(account_id);
+        match source {
+            BonusSource::Mint => self.token.internal_deposit(account_id, amount),
+            BonusSource::Reserve => {
+                let owner_id = self.owner_id.clone();
+                self.token.internal_transfer(&owner_id, account_id, amount, None)
+            }
+        }
+    }
+
+    /// Pay referral bonuses for a purchase of `amount` tokens made by `buyer_id`
+    pub(crate) fn internal_reward_referral(&mut self, buyer_id: &AccountId, referrer_id: &AccountId, amount: Balance) {
+        assert_ne!(buyer_id, referrer_id, "Self-referral is not allowed");
+        assert_ne!(&self.owner_id, referrer_id, "Owner can't be a referrer");
+
+        let config = self.referral_config.clone();
+        if config.referrer_bonus_bps == 0 && config.buyer_bonus_bps == 0 {
+            // The program is disabled, nothing is paid or tracked
+            return;
+        }
+        let mut stats = self.referral_stats.get(referrer_id).unwrap_or_default();
+        let mut remaining_cap = config.max_bonus_per_referrer.0.saturating_sub(stats.total_bonus());
+
+        let referrer_bonus = std::cmp::min(
+            amount * u128::from(config.referrer_bonus_bps) / BPS_DENOMINATOR,
+            remaining_cap,
+        );
+        remaining_cap -= referrer_bonus;
+        let buyer_bonus = std::cmp::min(
+            amount * u128::from(config.buyer_bonus_bps) / BPS_DENOMINATOR,
+            remaining_cap,
+        );
+
+        self.internal_credit_bonus(referrer_id, referrer_bonus, config.source);
+        self.internal_credit_bonus(buyer_id, buyer_bonus, config.source);
+
+        stats.referred_purchases += 1;
+        stats.referred_amount = U128(stats.referred_amount.0 + amount);
+        stats.referrer_bonus = U128(stats.referrer_bonus.0 + referrer_bonus);
+        stats.buyer_bonus = U128(stats.buyer_bonus.0 + buyer_bonus);
+        self.referral_stats.insert(referrer_id, &stats);
+
+        log!(
+            "Referral bonus for purchase by @{}: @{} got {}, buyer got {} ${}",
+            buyer_id,
+            referrer_id,
+            referrer_bonus,
+            buyer_bonus,
+            &self.metadata.get().unwrap().symbol,
+        );
+    }
+
+    /// Promo credits balance of the account, zero if the credits have expired
+    pub(crate) fn internal_promo_balance(&self, account_id: &AccountId) -> Balance {
+        match self.promo_credits.get(account_id) {
+            Some(promo_credit) if env::block_timestamp() < promo_credit.expires_at.0 => promo_credit.balance.0,
+            _ => 0,
+        }
+    }
+
+    /// Charge the account spending promo credits first and the regular FT balance afterwards
+    pub(crate) fn internal_charge(&mut self, account_id: &AccountId, balance_to_charge: Balance) {
+        let promo_balance = self.internal_promo_balance(account_id);
+        let promo_to_spend = std::cmp::min(promo_balance, balance_to_charge);
+        if promo_balance == promo_to_spend {
+            self.promo_credits.remove(account_id);
+        } else {
+            let mut promo_credit = self.promo_credits.get(account_id).unwrap();
+            promo_credit.balance = U128(promo_balance - promo_to_spend);
+            self.promo_credits.insert(account_id, &promo_credit);
+        }
+        if promo_to_spend > 0 {
+            log!("Account @{} charged for {} promo credits", account_id, promo_to_spend);
+        }
+
+        let balance_to_burn = balance_to_charge - promo_to_spend;
+        if balance_to_burn == 0 {
+            return;
+        }
+        // Accounts with promo credits only may be unregistered, they mustn't abort the rest of the charge list
+        let account_available_balance = match self.token.accounts.get(account_id) {
+            Some(balance) if balance > 0 => balance,
+            _ => {
+                log!(
+                    "Account @{} has no balance left. Supposed to charge {} ${}",
+                    account_id,
+                    balance_to_burn,
+                    &self.metadata.get().unwrap().symbol,
+                );
+                return;
+            }
+        };
+        if account_available_balance >= balance_to_burn {
+            self.token.internal_withdraw(account_id, balance_to_burn);
+            log!(
+                "Account @{} charged for {} ${}",
+                account_id,
+                balance_to_burn,
+                &self.metadata.get().unwrap().symbol,
+            );
+        } else {
+            self.token.internal_withdraw(account_id, account_available_balance);
+            log!(
+                "Account @{} charged for entire balance ({}). Supposed to charge {} ${}",
+                account_id,
+                account_available_balance,
+                balance_to_burn,
+                &self.metadata.get().unwrap().symbol,
+            );
+        }
+    }
+
+    /// Move tokens between accounts registering the receiver if needed
+    pub(crate) fn internal_move_tokens(&mut self, sender_id: &AccountId, receiver_id: &AccountId, amount: Balance) {
+        if sender_id == receiver_id || amount == 0 {
+            return;
+        }
+        self.internal_register_account_if_needed(receiver_id);
+        self.token.internal_transfer(sender_id, receiver_id, amount, None);
+    }
+
+    /// Take tokens out of the account's balance into an airdrop escrow. Escrowed tokens stay in the total supply
+    pub(crate) fn internal_escrow_tokens(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self.token.internal_unwrap_balance_of(account_id);
+        let new_balance = balance.checked_sub(amount).expect("The account doesn't have enough balance");
+        self.token.accounts.insert(account_id, &new_balance);
+    }
+
+    /// Credit escrowed tokens to the account registering it if needed
+    pub(crate) fn internal_release_escrow(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        self.internal_register_account_if_needed(account_id);
+        let balance = self.token.internal_unwrap_balance_of(account_id);
+        let new_balance = balance.checked_add(amount).expect("Balance overflow");
+        self.token.accounts.insert(account_id, &new_balance);
+    }
+
+    /// Charge the relay cost from the gas budget. The spent deposited NEAR goes to the treasury (the owner),
+    /// the converted part has already been paid with FT tokens
+    pub(crate) fn internal_charge_gas_budget(&mut self, account_id: &AccountId, cost: Balance) {
+        let mut gas_budget = self.gas_budgets.get(account_id).unwrap_or_default();
+        let deposited_spent = gas_budget.spend(cost);
+        self.gas_budgets.insert(account_id, &gas_budget);
+        if deposited_spent > 0 {
+            Promise::new(self.owner_id.clone()).transfer(deposited_spent);
+        }
+    }
+
+    /// Check and set the claimed flag of the airdrop leaf. Returns `false` if it was already set
+    pub(crate) fn internal_set_airdrop_claimed(&mut self, airdrop_id: u64, index: u64) -> bool {
+        let word_key = (airdrop_id, index / CLAIMED_BITMAP_WORD_BITS);
+        let mask = 1u128 << (index % CLAIMED_BITMAP_WORD_BITS);
+        let word = self.airdrop_claims.get(&word_key).unwrap_or(0);
+        if word & mask != 0 {
+            return false;
+        }
+        self.airdrop_claims.insert(&word_key, &(word | mask));
+        true
+    }
 }
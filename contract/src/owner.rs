@@ -4,7 +4,7 @@ use crate::*;
 impl Contract {
     /// Create additional provided amount of FT tokens in circulation
     pub fn print_tokens(&mut self, amount: U128) {
-        assert_eq!(self.owner_id, env::signer_account_id(), "Signer must be an owner");
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
         let tokens_to_print: u128 = amount.into();
         self.token.internal_deposit(&self.owner_id, tokens_to_print);
         log!("{} tokens were printed and deposited to owner's account", tokens_to_print);
@@ -12,35 +12,220 @@ impl Contract {
 
     /// Set a new exchange price for FT token
     pub fn replace_exchange_price(&mut self, new_price_in_yocto_nears: U128) {
-        assert_eq!(self.owner_id, env::signer_account_id(), "Signer must be an owner");
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
         self.exchange_price_in_yocto_near = new_price_in_yocto_nears;
         log!("Exchange price has been changed to the new value (in yoctoNEARS) of {:?}", new_price_in_yocto_nears)
     }
 
-    /// Charge specified users for a specified amount of FT tokens
+    /// Charge specified users for a specified amount of FT tokens.
+    /// Promo credits are spent before the regular balance
     pub fn charge_users(&mut self, charge_list: Vec<(ValidAccountId, Balance)>) {
-        assert_eq!(self.owner_id, env::signer_account_id(), "Signer must be an owner");
-        for (valid_account_id, balance_to_burn) in charge_list.iter() {
-            let account_id: String = valid_account_id.clone().into();
-            let account_available_balance = self.token.accounts.get(&account_id).unwrap_or(0);
-            if account_available_balance >= *balance_to_burn {
-                self.token.internal_withdraw(&account_id, *balance_to_burn);
-                log!(
-                    "Account @{} charged for {} ${}",
-                    account_id,
-                    balance_to_burn,
-                    &self.metadata.get().unwrap().symbol,
-                );
-            } else {
-                self.token.internal_withdraw(&account_id, account_available_balance);
-                log!(
-                    "Account @{} charged for entire balance ({}). Supposed to charge {} ${}",
-                    account_id,
-                    account_available_balance,
-                    balance_to_burn,
-                    &self.metadata.get().unwrap().symbol,
-                );
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        for (valid_account_id, balance_to_charge) in charge_list.iter() {
+            self.internal_charge(valid_account_id.as_ref(), *balance_to_charge);
+        }
+    }
+
+    /// Set the referral program settings
+    pub fn set_referral_config(&mut self, config: ReferralConfig) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        config.assert_valid();
+        self.referral_config = config;
+        log!("Referral program settings have been updated");
+    }
+
+    /// Register a promo code by its sha256 hash.
+    /// Code hashes can't be reused: accounts which redeemed a removed code can't redeem the code with the same hash again
+    pub fn add_promo_code(&mut self, code_hash: Base64VecU8, amount: U128, max_redemptions: u32, expires_at: U64) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert_eq!(code_hash.0.len(), PROMO_CODE_HASH_LEN, "Promo code hash must be a sha256 hash");
+        assert!(amount.0 > 0, "Promo code amount must be positive");
+        assert!(max_redemptions > 0, "Promo code must be redeemable at least once");
+        assert!(expires_at.0 > env::block_timestamp(), "Promo code expiration must be in the future");
+        assert!(self.promo_codes.get(&code_hash.0).is_none(), "Promo code already exists");
+        let promo_code = PromoCode {
+            amount,
+            max_redemptions,
+            redemptions: 0,
+            expires_at,
+        };
+        self.promo_codes.insert(&code_hash.0, &promo_code);
+        log!("Promo code has been added: {} tokens, {} redemptions", amount.0, max_redemptions);
+    }
+
+    /// Remove the promo code so it can't be redeemed anymore.
+    /// Redemption records are kept, so the accounts which have redeemed it stay blocked if the same hash is added again
+    pub fn remove_promo_code(&mut self, code_hash: Base64VecU8) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert!(self.promo_codes.remove(&code_hash.0).is_some(), "Promo code doesn't exist");
+        log!("Promo code has been removed");
+    }
+
+    /// Grant non-transferable promo credits to the account. Unspent credits expire at `expires_at`
+    pub fn grant_promo_credits(&mut self, account_id: ValidAccountId, amount: U128, expires_at: U64) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert!(expires_at.0 > env::block_timestamp(), "Promo credits expiration must be in the future");
+        let account_id: AccountId = account_id.into();
+        let promo_balance = self.internal_promo_balance(&account_id);
+        let promo_credit = PromoCredit {
+            balance: U128(promo_balance + amount.0),
+            expires_at,
+        };
+        self.promo_credits.insert(&account_id, &promo_credit);
+        log!(
+            "Account @{} has been granted {} promo credits. Promo balance is {} until {}",
+            account_id,
+            amount.0,
+            promo_credit.balance.0,
+            expires_at.0,
+        );
+    }
+
+    /// Create an airdrop moving `total` tokens from the owner's account to its escrow. Returns the airdrop id
+    pub fn create_airdrop(&mut self, merkle_root: Base64VecU8, total: U128, expires_at: U64) -> u64 {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert_eq!(merkle_root.0.len(), MERKLE_NODE_LEN, "Merkle root must be a sha256 hash");
+        assert!(total.0 > 0, "Airdrop total must be positive");
+        assert!(expires_at.0 > env::block_timestamp(), "Airdrop expiration must be in the future");
+        let owner_id = self.owner_id.clone();
+        self.internal_escrow_tokens(&owner_id, total.0);
+        self.airdrops.push(&Airdrop {
+            merkle_root,
+            total,
+            remaining: total,
+            expires_at,
+            reclaimed: false,
+        });
+        let airdrop_id = self.airdrops.len() - 1;
+        log!("Airdrop {} has been created for {} tokens", airdrop_id, total.0);
+        airdrop_id
+    }
+
+    /// Return unclaimed tokens of the expired airdrop to the owner's account
+    pub fn reclaim_airdrop(&mut self, airdrop_id: u64) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        let mut airdrop = self.airdrops.get(airdrop_id).expect("Airdrop doesn't exist");
+        assert!(env::block_timestamp() >= airdrop.expires_at.0, "Airdrop hasn't expired yet");
+        assert!(!airdrop.reclaimed, "Airdrop has already been reclaimed");
+        let unclaimed = airdrop.remaining.0;
+        airdrop.remaining = U128(0);
+        airdrop.reclaimed = true;
+        self.airdrops.replace(airdrop_id, &airdrop);
+        let owner_id = self.owner_id.clone();
+        self.internal_release_escrow(&owner_id, unclaimed);
+        log!("Airdrop {} has been closed. {} unclaimed tokens returned to owner", airdrop_id, unclaimed);
+    }
+
+    /// Allow `http://localhost` subscription endpoints. Meant for development deployments only
+    pub fn set_localhost_endpoints_allowed(&mut self, allowed: bool) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        self.allow_localhost_endpoints = allowed;
+        log!("Localhost endpoints have been {}", if allowed { "allowed" } else { "disallowed" });
+    }
+
+    /// Publish a new encryption public key of the dispatcher. Returns the key version.
+    /// New encrypted endpoints must be sealed for the latest key, older ones keep their key version
+    pub fn publish_dispatcher_key(&mut self, public_key: Base64VecU8) -> u64 {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert_eq!(public_key.0.len(), DISPATCHER_KEY_LEN, "Dispatcher key must be an X25519 public key");
+        let version = self.dispatcher_keys.len();
+        self.dispatcher_keys.push(&DispatcherKey {
+            version,
+            public_key,
+            published_at: env::block_timestamp().into(),
+        });
+        log!("Dispatcher key version {} has been published", version);
+        version
+    }
+
+    /// Report the notification of the subscription has been delivered. One-shot subscriptions are deleted
+    pub fn report_delivery(&mut self, account_id: ValidAccountId, subscription_id: SubscriptionId) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert!(
+            self.internal_record_delivery(account_id.as_ref(), subscription_id),
+            "Subscription doesn't exist",
+        );
+    }
+
+    /// Delete one-shot subscriptions of the accounts which haven't been delivered within `ONE_SHOT_WATCH_TTL`
+    pub fn prune_stale_watches(&mut self, account_ids: Vec<ValidAccountId>) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        let now = env::block_timestamp();
+        for account_id in account_ids {
+            if let Some(mut subscription_list) = self.internal_subscriptions_of(account_id.as_ref()) {
+                let subscriptions_count = subscription_list.len();
+                let (stale, fresh): (Vec<_>, Vec<_>) = subscription_list.into_iter().partition(|subscription| {
+                    subscription.is_one_shot() && now >= subscription.created_at.0 + ONE_SHOT_WATCH_TTL
+                });
+                subscription_list = fresh;
+                if subscription_list.len() < subscriptions_count {
+                    self.internal_save_subscriptions(account_id.as_ref(), &subscription_list);
+                    let mut refund = 0;
+                    for subscription in stale {
+                        self.delivery_results.remove(&(account_id.to_string(), subscription.id));
+                        refund += subscription.storage_deposit.0;
+                    }
+                    if refund > 0 {
+                        Promise::new(account_id.to_string()).transfer(refund);
+                    }
+                    log!(
+                        "{} stale one-shot subscriptions of @{} have been pruned",
+                        subscriptions_count - subscription_list.len(),
+                        account_id,
+                    );
+                }
             }
         }
     }
+
+    /// Relay the event payload to the `ContractCall` delivery target of the subscription.
+    /// The call is prepaid with `gas`, its cost is charged from the gas budget of the subscription owner
+    /// at `gas_price`, the gas price of the block the relaying transaction is sent in.
+    /// Contracts can't read the gas price, so the owner passes it
+    pub fn relay_contract_call(
+        &mut self,
+        account_id: ValidAccountId,
+        subscription_id: SubscriptionId,
+        payload: String,
+        gas: U64,
+        gas_price: U128,
+    ) -> Promise {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        assert!(gas.0 > 0 && gas.0 <= MAX_RELAY_GAS, "Relay gas must be from 1 to {}", MAX_RELAY_GAS);
+        assert!(gas_price.0 >= MIN_GAS_PRICE, "Gas price can't be lower than {}", MIN_GAS_PRICE);
+        assert!(payload.len() <= MAX_RELAY_PAYLOAD_LEN, "Payload can't be longer than {} bytes", MAX_RELAY_PAYLOAD_LEN);
+        let subscription = self
+            .internal_subscriptions_of(account_id.as_ref())
+            .and_then(|subscription_list| subscription_list.into_iter().find(|subscription| subscription.id == subscription_id))
+            .expect("Subscription doesn't exist");
+        assert!(subscription.enabled, "Subscription is disabled");
+        assert!(!subscription.is_expired(env::block_timestamp()), "Subscription has expired");
+        let (receiver_id, method) = subscription
+            .delivery
+            .into_iter()
+            .find_map(|delivery| match delivery {
+                Delivery::ContractCall { receiver_id, method } => Some((receiver_id, method)),
+                _ => None,
+            })
+            .expect("Subscription doesn't have a contract call delivery target");
+        self.internal_charge_gas_budget(account_id.as_ref(), relay_cost(gas.0, gas_price.0));
+        log!("Relaying subscription {} of @{} to {}.{}", subscription_id, account_id, receiver_id, method);
+        Promise::new(receiver_id.into())
+            .function_call(method.into_bytes(), payload.into_bytes(), 0, gas.0)
+            .then(ext_self::on_contract_call_delivered(
+                account_id.into(),
+                subscription_id,
+                &env::current_account_id(),
+                0,
+                DELIVERY_CALLBACK_GAS,
+            ))
+    }
+
+    /// Convert up to `limit` subscription lists of the legacy layout. Returns the number of lists left to migrate
+    pub fn migrate_subscriptions(&mut self, limit: u64) -> u64 {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Predecessor must be an owner");
+        let migrated_count = self.internal_migrate_subscriptions(limit);
+        log!("Subscriptions of {} accounts have been migrated", migrated_count);
+        self.legacy_subscriptions.len()
+    }
 }
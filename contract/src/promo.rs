@@ -0,0 +1,42 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use crate::*;
+
+/// Length of the sha256 hash of a promo code
+pub const PROMO_CODE_HASH_LEN: usize = 32;
+
+/// Promo code redeemable for FT tokens from the owner's reserve
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PromoCode {
+    /// Amount of tokens credited per redemption
+    pub amount: U128,
+    /// How many times the code can be redeemed in total
+    pub max_redemptions: u32,
+    /// How many times the code has been redeemed so far
+    pub redemptions: u32,
+    /// Block timestamp (in nanoseconds) after which the code can't be redeemed
+    pub expires_at: U64,
+}
+
+/// Non-transferable promotional credits spent by billing before the regular FT balance
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PromoCredit {
+    /// Amount of unspent credits
+    pub balance: U128,
+    /// Block timestamp (in nanoseconds) after which unspent credits are void
+    pub expires_at: U64,
+}
+
+/// Billing balances of an account
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Credits {
+    /// Regular FT balance
+    pub balance: U128,
+    /// Unexpired promo credits
+    pub promo_balance: U128,
+    /// Expiration of the promo credits, if any
+    pub promo_expires_at: Option<U64>,
+}
@@ -2,91 +2,541 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-    /// Exchange NEAR tokens for FT tokens based on current exchange price
+    /// Exchange NEAR tokens for FT tokens based on current exchange price.
+    /// Optional `referrer_id` gets a referral bonus according to the referral program settings
     #[payable]
-    pub fn buy_ft_tokens(&mut self) {
+    pub fn buy_ft_tokens(&mut self, referrer_id: Option<ValidAccountId>) {
         let attached_deposit = env::attached_deposit();
-        let signer_account_id = env::signer_account_id();
+        let predecessor_account_id = env::predecessor_account_id();
 
-        if self.token.accounts.get(&signer_account_id).is_none() {
-            self.token.internal_register_account(&signer_account_id);
-        }
+        self.internal_register_account_if_needed(&predecessor_account_id);
 
-        // Calculate how many ft_tokens signer can get in exchange for the attached_deposit
+        // Calculate how many ft_tokens the caller can get in exchange for the attached_deposit
         let affordable_amount: u128 = attached_deposit / self.exchange_price_in_yocto_near.0;
 
         // Calculate surplus that should be refunded
         let surplus: u128 = attached_deposit - (affordable_amount * self.exchange_price_in_yocto_near.0);
-        // Transfer bought ft_tokens to the signer
-        self.token.internal_transfer(&self.owner_id, &signer_account_id, affordable_amount, None);
+        // Transfer bought ft_tokens to the caller
+        self.token.internal_transfer(&self.owner_id, &predecessor_account_id, affordable_amount, None);
 
         // Send spent yoctoNEARs to the treasury (self.owner_id)
         Promise::new(self.owner_id.clone()).transfer(attached_deposit - surplus);
-        // Refund surplus yoctoNEARs to the signer
-        Promise::new(signer_account_id.clone()).transfer(surplus);
+        // Refund surplus yoctoNEARs to the caller
+        Promise::new(predecessor_account_id.clone()).transfer(surplus);
         log!(
             "Account @{} has bought {} ${} tokens. Refunded {} yoctoNEARS",
-            signer_account_id,
+            predecessor_account_id,
             affordable_amount,
             &self.metadata.get().unwrap().symbol,
             surplus,
         );
+
+        if let Some(referrer_id) = referrer_id {
+            self.internal_reward_referral(&predecessor_account_id, referrer_id.as_ref(), affordable_amount);
+        }
+    }
+
+    /// Redeem the promo code for FT tokens from the owner's reserve
+    pub fn redeem_promo(&mut self, code: String) {
+        let predecessor_account_id = env::predecessor_account_id();
+        let code_hash = env::sha256(code.as_bytes());
+        let mut promo_code = self.promo_codes.get(&code_hash).expect("Promo code doesn't exist");
+        assert!(env::block_timestamp() < promo_code.expires_at.0, "Promo code has expired");
+        assert!(promo_code.redemptions < promo_code.max_redemptions, "Promo code has been fully redeemed");
+        assert!(
+            self.promo_redemptions.insert(&(code_hash.clone(), predecessor_account_id.clone())),
+            "Account {} has already redeemed this promo code",
+            &predecessor_account_id,
+        );
+
+        promo_code.redemptions += 1;
+        self.promo_codes.insert(&code_hash, &promo_code);
+        self.internal_credit_bonus(&predecessor_account_id, promo_code.amount.0, BonusSource::Reserve);
+        log!(
+            "Account @{} has redeemed a promo code for {} ${}",
+            predecessor_account_id,
+            promo_code.amount.0,
+            &self.metadata.get().unwrap().symbol,
+        );
     }
 
+    /// Claim tokens of the airdrop proving the `(index, amount, caller)` leaf is in its merkle tree
+    pub fn claim_airdrop(&mut self, airdrop_id: u64, index: u64, amount: U128, proof: Vec<Base64VecU8>) {
+        let predecessor_account_id = env::predecessor_account_id();
+        let mut airdrop = self.airdrops.get(airdrop_id).expect("Airdrop doesn't exist");
+        assert!(env::block_timestamp() < airdrop.expires_at.0 && !airdrop.reclaimed, "Airdrop has expired");
+        assert!(
+            verify_merkle_proof(airdrop_leaf(index, amount.0, &predecessor_account_id), &proof, &airdrop.merkle_root.0),
+            "Invalid merkle proof",
+        );
+        assert!(airdrop.remaining.0 >= amount.0, "Airdrop doesn't have enough tokens left");
+        assert!(self.internal_set_airdrop_claimed(airdrop_id, index), "Airdrop has already been claimed");
+
+        airdrop.remaining = U128(airdrop.remaining.0 - amount.0);
+        self.airdrops.replace(airdrop_id, &airdrop);
+        self.internal_release_escrow(&predecessor_account_id, amount.0);
+        log!(
+            "Account @{} has claimed {} ${} from airdrop {}",
+            predecessor_account_id,
+            amount.0,
+            &self.metadata.get().unwrap().symbol,
+            airdrop_id,
+        );
+    }
+
+    /// Subscribe the caller for the event. Finality defaults to `Final`,
+    /// `min_confirmations` of `0` or `None` means no delay. The subscription expires at `expires_at`
+    /// or after `max_deliveries` deliveries if set. The storage is paid with the attached deposit, the rest is refunded
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
     pub fn create_subscription(
         &mut self,
-        endpoint: String,
+        delivery: Vec<subscriptions::Delivery>,
         event: subscriptions::Event,
+        finality: Option<subscriptions::Finality>,
+        min_confirmations: Option<u32>,
+        expires_at: Option<U64>,
+        max_deliveries: Option<u64>,
+        secret_hash: Option<Base64VecU8>,
+    ) {
+        let min_confirmations = subscriptions::assert_valid(subscriptions::validate_min_confirmations(min_confirmations));
+        subscriptions::assert_valid(subscriptions::validate_expiration(expires_at, max_deliveries));
+        if let Some(secret_hash) = &secret_hash {
+            subscriptions::assert_valid(subscriptions::validate_secret_hash(secret_hash));
+        }
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let subscription_id = self.internal_create_subscription(&predecessor_account_id, delivery, event, |subscription| {
+            subscription.finality = finality.unwrap_or(subscriptions::Finality::Final);
+            subscription.min_confirmations = min_confirmations;
+            subscription.expires_at = expires_at;
+            subscription.max_deliveries = max_deliveries;
+            subscription.secret_hash = secret_hash;
+        });
+        self.internal_settle_subscription_storage(&predecessor_account_id, subscription_id, initial_storage_usage);
+    }
+
+    /// Update the given settings of the caller's subscription leaving the rest intact.
+    /// Storage growth is paid with the attached deposit, released storage is refunded
+    #[payable]
+    pub fn update_subscription(
+        &mut self,
+        id: SubscriptionId,
+        delivery: Option<Vec<subscriptions::Delivery>>,
+        event: Option<subscriptions::Event>,
+        enabled: Option<bool>,
+        finality: Option<subscriptions::Finality>,
+        min_confirmations: Option<u32>,
+    ) {
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let delivery = delivery.map(|delivery| self.internal_normalize_delivery(delivery));
+        self.internal_update_subscription(&predecessor_account_id, id, |subscription| {
+            if let Some(delivery) = delivery {
+                subscription.delivery = delivery;
+            }
+            if let Some(event) = event {
+                event.assert_valid();
+                subscription.event = event;
+            }
+            if let Some(enabled) = enabled {
+                subscription.enabled = enabled;
+            }
+            if let Some(finality) = finality {
+                subscription.finality = finality;
+            }
+            if min_confirmations.is_some() {
+                subscription.min_confirmations = subscriptions::assert_valid(subscriptions::validate_min_confirmations(min_confirmations));
+            }
+        });
+        self.internal_settle_subscription_storage(&predecessor_account_id, id, initial_storage_usage);
+    }
+
+    /// Replace the expiration limits of the caller's subscription, `None` removes the limit
+    #[payable]
+    pub fn set_subscription_expiration(&mut self, id: SubscriptionId, expires_at: Option<U64>, max_deliveries: Option<u64>) {
+        subscriptions::assert_valid(subscriptions::validate_expiration(expires_at, max_deliveries));
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        self.internal_update_subscription(&predecessor_account_id, id, |subscription| {
+            subscription.expires_at = expires_at;
+            subscription.max_deliveries = max_deliveries;
+        });
+        self.internal_settle_subscription_storage(&predecessor_account_id, id, initial_storage_usage);
+    }
+
+    /// Watch a single transaction. The subscription is deleted once the operator has delivered the notification
+    #[payable]
+    pub fn watch_transaction(&mut self, tx_hash: Base58CryptoHash, delivery: Vec<subscriptions::Delivery>) -> SubscriptionId {
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let subscription_id = self.internal_create_subscription(&predecessor_account_id, delivery, subscriptions::Event::WatchTransaction(tx_hash), |_| {});
+        self.internal_settle_subscription_storage(&predecessor_account_id, subscription_id, initial_storage_usage);
+        subscription_id
+    }
+
+    /// Watch a single receipt. The subscription is deleted once the operator has delivered the notification
+    #[payable]
+    pub fn watch_receipt(&mut self, receipt_id: Base58CryptoHash, delivery: Vec<subscriptions::Delivery>) -> SubscriptionId {
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let subscription_id = self.internal_create_subscription(&predecessor_account_id, delivery, subscriptions::Event::WatchReceipt(receipt_id), |_| {});
+        self.internal_settle_subscription_storage(&predecessor_account_id, subscription_id, initial_storage_usage);
+        subscription_id
+    }
+
+    /// Commit to a new webhook signing secret of the caller's subscription by its sha256 hash.
+    /// `None` removes the secret, so the notifications are sent unsigned
+    #[payable]
+    pub fn rotate_subscription_secret(&mut self, id: SubscriptionId, new_hash: Option<Base64VecU8>) {
+        if let Some(new_hash) = &new_hash {
+            subscriptions::assert_valid(subscriptions::validate_secret_hash(new_hash));
+        }
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let is_removed = new_hash.is_none();
+        self.internal_update_subscription(&predecessor_account_id, id, |subscription| {
+            subscription.secret_hash = new_hash;
+        });
+        self.internal_settle_subscription_storage(&predecessor_account_id, id, initial_storage_usage);
+        if is_removed {
+            log!("Signing secret of subscription {} of @{} has been removed", id, predecessor_account_id);
+        } else {
+            log!("Signing secret of subscription {} of @{} has been rotated", id, predecessor_account_id);
+        }
+    }
+
+    /// Delete the expired subscriptions of up to `limit` accounts starting at `from_index` of the subscribed accounts.
+    /// Subscription lists of the legacy layout are migrated first, each of them counts against `limit`.
+    /// Storage deposits are refunded to the subscription owners less `KEEPER_REWARD_BPS` paid to the caller.
+    /// Accounts left without subscriptions are removed and the last account takes their index,
+    /// so a sweep with the returned `next_index` checks every account
+    pub fn prune_expired(&mut self, from_index: u64, limit: u64) -> subscriptions::PruneResult {
+        let now = env::block_timestamp();
+        let mut checked_count = self.internal_migrate_subscriptions(limit);
+        let mut index = from_index;
+        let mut pruned_count = 0;
+        let mut keeper_reward = 0;
+        while checked_count < limit && index < self.subscriptions.len() {
+            checked_count += 1;
+            let account_id = self.subscriptions.keys_as_vector().get(index).unwrap();
+            let subscription_list = self.subscriptions.get(&account_id).unwrap_or_default();
+            let (expired, active): (Vec<_>, Vec<_>) = subscription_list
+                .into_iter()
+                .partition(|subscription| subscription.is_expired(now));
+            if expired.is_empty() {
+                index += 1;
+                continue;
+            }
+            self.internal_save_subscriptions(&account_id, &active);
+            // The removed account is replaced with the last one, which is checked at the same index
+            if !active.is_empty() {
+                index += 1;
+            }
+            let mut refund = 0;
+            for subscription in expired.iter() {
+                self.delivery_results.remove(&(account_id.clone(), subscription.id));
+                let reward = subscription.storage_deposit.0 * subscriptions::KEEPER_REWARD_BPS / BPS_DENOMINATOR;
+                keeper_reward += reward;
+                refund += subscription.storage_deposit.0 - reward;
+            }
+            if refund > 0 {
+                Promise::new(account_id.clone()).transfer(refund);
+            }
+            pruned_count += expired.len() as u64;
+            log!("{} expired subscriptions of @{} have been pruned", expired.len(), account_id);
+        }
+        if keeper_reward > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(keeper_reward);
+        }
+        subscriptions::PruneResult {
+            pruned: pruned_count,
+            next_index: if index < self.subscriptions.len() { Some(index) } else { None },
+        }
+    }
+
+    /// Top up the gas budget of the relayed contract calls with the attached NEAR.
+    /// The storage of the first deposit is paid with the attached deposit and can't be withdrawn
+    #[payable]
+    pub fn deposit_gas_budget(&mut self) {
+        let attached_deposit = env::attached_deposit();
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let mut gas_budget = self.gas_budgets.get(&predecessor_account_id).unwrap_or_default();
+        gas_budget.deposited = U128(gas_budget.deposited.0 + attached_deposit);
+        self.gas_budgets.insert(&predecessor_account_id, &gas_budget);
+        let storage_cost = Balance::from(env::storage_usage().saturating_sub(initial_storage_usage)) * env::storage_byte_cost();
+        assert!(
+            attached_deposit > storage_cost,
+            "Attach more than {} yoctoNEAR to cover the gas budget storage",
+            storage_cost,
+        );
+        // The budget has a fixed size, so the storage usage stays the same
+        gas_budget.deposited = U128(gas_budget.deposited.0 - storage_cost);
+        self.gas_budgets.insert(&predecessor_account_id, &gas_budget);
+        log!(
+            "Account @{} has deposited {} yoctoNEAR to the gas budget",
+            predecessor_account_id,
+            attached_deposit - storage_cost,
+        );
+    }
+
+    /// Top up the gas budget with FT tokens converted at the exchange price. Converted budget can't be withdrawn.
+    /// Requires exactly 1 yoctoNEAR attached. The owner can't convert, the tokens would stay in its own reserve
+    #[payable]
+    pub fn convert_tokens_to_gas_budget(&mut self, amount: U128) {
+        assert_one_yocto();
+        assert!(amount.0 > 0, "Amount must be positive");
+        let predecessor_account_id = env::predecessor_account_id();
+        let owner_id = self.owner_id.clone();
+        assert_ne!(predecessor_account_id, owner_id, "Owner can't convert tokens to the gas budget");
+        self.internal_move_tokens(&predecessor_account_id, &owner_id, amount.0);
+        let converted = amount.0 * self.exchange_price_in_yocto_near.0;
+        let mut gas_budget = self.gas_budgets.get(&predecessor_account_id).unwrap_or_default();
+        gas_budget.converted = U128(gas_budget.converted.0 + converted);
+        self.gas_budgets.insert(&predecessor_account_id, &gas_budget);
+        log!(
+            "Account @{} has converted {} ${} to {} yoctoNEAR of the gas budget",
+            predecessor_account_id,
+            amount.0,
+            &self.metadata.get().unwrap().symbol,
+            converted,
+        );
+    }
+
+    /// Withdraw the deposited NEAR of the gas budget. Requires exactly 1 yoctoNEAR attached
+    #[payable]
+    pub fn withdraw_gas_budget(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        let predecessor_account_id = env::predecessor_account_id();
+        let mut gas_budget = self.gas_budgets.get(&predecessor_account_id).unwrap_or_default();
+        assert!(gas_budget.deposited.0 >= amount.0, "Not enough deposited gas budget");
+        gas_budget.deposited = U128(gas_budget.deposited.0 - amount.0);
+        self.gas_budgets.insert(&predecessor_account_id, &gas_budget);
+        log!("Account @{} has withdrawn {} yoctoNEAR from the gas budget", predecessor_account_id, amount.0);
+        Promise::new(predecessor_account_id).transfer(amount.0)
+    }
+
+    /// Record the outcome of the relayed contract call, see `relay_contract_call`
+    #[private]
+    pub fn on_contract_call_delivered(&mut self, account_id: AccountId, subscription_id: SubscriptionId) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        let subscription_list = self.internal_subscriptions_of(&account_id);
+        if !matches!(subscription_list, Some(list) if list.iter().any(|subscription| subscription.id == subscription_id)) {
+            // Deleted or pruned while the call was in flight, the results would be left without the subscription
+            log!("Subscription {} of @{} has been deleted before its contract call finished", subscription_id, account_id);
+            return succeeded;
+        }
+        let key = (account_id.clone(), subscription_id);
+        let mut delivery_results = self.delivery_results.get(&key).unwrap_or_default();
+        if succeeded {
+            delivery_results.succeeded += 1;
+        } else {
+            delivery_results.failed += 1;
+        }
+        delivery_results.last_delivered_at = env::block_timestamp().into();
+        delivery_results.last_succeeded = succeeded;
+        self.delivery_results.insert(&key, &delivery_results);
+        log!(
+            "Contract call of subscription {} of @{} has {}",
+            subscription_id,
+            account_id,
+            if succeeded { "succeeded" } else { "failed" },
+        );
+        if succeeded {
+            self.internal_record_delivery(&account_id, subscription_id);
+        }
+        succeeded
+    }
+
+    pub fn delete_subscription(
+        &mut self,
+        id: SubscriptionId,
     ) {
-        let signer_account_id = env::signer_account_id();
-        let mut subscription_list = self.get_or_create_user_subscription_list(&signer_account_id);
-        let subscription_id = subscription_list
+        let predecessor_account_id = env::predecessor_account_id();
+        if self.internal_remove_subscription(&predecessor_account_id, id).is_none() {
+            panic!(
+                "Account {} doesn't have the Subscription with id {}",
+                &predecessor_account_id,
+                id,
+            );
+        }
+    }
+}
+
+
+impl Contract {
+    /// Add the subscription with the default settings changed by `configure`. Returns the id of the subscription
+    pub(crate) fn internal_create_subscription<F: FnOnce(&mut subscriptions::UserSubscription)>(
+        &mut self,
+        account_id: &AccountId,
+        delivery: Vec<subscriptions::Delivery>,
+        event: subscriptions::Event,
+        configure: F,
+    ) -> SubscriptionId {
+        let delivery = self.internal_normalize_delivery(delivery);
+        event.assert_valid();
+        let mut subscription_list = self.internal_subscriptions_of(account_id).unwrap_or_default();
+        // Accounts subscribed before the counter was introduced continue after their largest id
+        let last_subscription_id = self.last_subscription_ids.get(account_id).unwrap_or_else(|| {
+            subscription_list
                 .iter()
                 .map(|subscription| subscription.id)
                 .max()
-                .unwrap_or(0) + 1;
-        let new_user_subscription = subscriptions::UserSubscription {
+                .unwrap_or(0)
+        });
+        let subscription_id = last_subscription_id
+            .checked_add(1)
+            .expect("Account has run out of subscription ids");
+        self.last_subscription_ids.insert(account_id, &subscription_id);
+        let mut new_user_subscription = subscriptions::UserSubscription {
             id: subscription_id,
             enabled: true,
-            endpoint,
+            delivery,
             event,
+            created_at: env::block_timestamp().into(),
+            finality: subscriptions::Finality::Final,
+            min_confirmations: None,
+            secret_hash: None,
+            expires_at: None,
+            max_deliveries: None,
+            deliveries: 0,
+            storage_deposit: U128(0),
         };
+        configure(&mut new_user_subscription);
         subscription_list.push(new_user_subscription);
-        self.subscriptions.insert(&signer_account_id, &subscription_list);
+        self.internal_save_subscriptions(account_id, &subscription_list);
+        subscription_id
     }
 
-    pub fn delete_subscription(
-        &mut self,
-        id: u8,
-    ) {
-        let signer_account_id = env::signer_account_id();
-        let mut subscription_list = self.get_or_create_user_subscription_list(&signer_account_id);
-        let index_of_subscription_to_delete = subscription_list
+    pub(crate) fn internal_remove_subscription(&mut self, account_id: &AccountId, id: SubscriptionId) -> Option<subscriptions::UserSubscription> {
+        let mut subscription_list = self.internal_subscriptions_of(account_id)?;
+        let index = subscription_list
             .iter()
-            .position(|subscription| subscription.id == id);
-        if let Some(index) = index_of_subscription_to_delete {
-            subscription_list.remove(index);
-            self.subscriptions.insert(&signer_account_id, &subscription_list);
+            .position(|subscription| subscription.id == id)?;
+        let subscription = subscription_list.remove(index);
+        self.internal_save_subscriptions(account_id, &subscription_list);
+        self.delivery_results.remove(&(account_id.clone(), id));
+        if subscription.storage_deposit.0 > 0 {
+            Promise::new(account_id.clone()).transfer(subscription.storage_deposit.0);
+        }
+        Some(subscription)
+    }
+
+    /// Count the delivered notification, one-shot subscriptions are deleted and their storage is refunded. Returns `false` if the subscription doesn't exist
+    pub(crate) fn internal_record_delivery(&mut self, account_id: &AccountId, subscription_id: SubscriptionId) -> bool {
+        let subscription = match self
+            .internal_subscriptions_of(account_id)
+            .and_then(|subscription_list| subscription_list.into_iter().find(|subscription| subscription.id == subscription_id))
+        {
+            Some(subscription) => subscription,
+            None => return false,
+        };
+        if subscription.is_one_shot() {
+            self.internal_remove_subscription(account_id, subscription_id);
+            log!("One-shot subscription {} of @{} has been delivered and deleted", subscription_id, account_id);
         } else {
-            panic!(
+            self.internal_update_subscription(account_id, subscription_id, |subscription| {
+                subscription.deliveries += 1;
+            });
+            log!("Subscription {} of @{} has been delivered", subscription_id, account_id);
+        }
+        true
+    }
+
+    /// Apply the change to the account's subscription and save it. Panics if the subscription doesn't exist
+    pub(crate) fn internal_update_subscription<F: FnOnce(&mut subscriptions::UserSubscription)>(
+        &mut self,
+        account_id: &AccountId,
+        id: SubscriptionId,
+        update: F,
+    ) {
+        let mut subscription_list = self.internal_subscriptions_of(account_id).unwrap_or_default();
+        match subscription_list.iter_mut().find(|subscription| subscription.id == id) {
+            Some(subscription) => update(subscription),
+            None => panic!(
                 "Account {} doesn't have the Subscription with id {}",
-                &signer_account_id,
+                account_id,
                 id,
-            );
+            ),
         }
+        self.internal_save_subscriptions(account_id, &subscription_list);
     }
-}
 
+    /// Canonical form of the delivery targets. Webhooks to `http://localhost` are accepted only if the owner allows it,
+    /// encrypted webhook URLs must be sealed for the latest dispatcher key
+    pub(crate) fn internal_normalize_delivery(&self, delivery: Vec<subscriptions::Delivery>) -> Vec<subscriptions::Delivery> {
+        let current_key_version = self.dispatcher_keys.len().checked_sub(1);
+        subscriptions::assert_valid(subscriptions::normalize_delivery(
+            delivery,
+            self.allow_localhost_endpoints,
+            current_key_version,
+        ))
+    }
 
-impl Contract {
-    fn get_or_create_user_subscription_list(&mut self, account_id: &AccountId) -> Vec<subscriptions::UserSubscription> {
-        match self.subscriptions.get(account_id) {
-            Some(subscription_list) => subscription_list,
-            None => {
-                let subscription_list: Vec<subscriptions::UserSubscription> = Vec::new();
-                self.subscriptions.insert(account_id, &subscription_list);
-                subscription_list
+    /// Subscriptions of the account, records of the legacy layout are converted
+    pub(crate) fn internal_subscriptions_of(&self, account_id: &AccountId) -> Option<Vec<subscriptions::UserSubscription>> {
+        self.subscriptions.get(account_id).or_else(|| {
+            self.legacy_subscriptions
+                .get(account_id)
+                .map(|subscription_list| subscription_list.into_iter().map(Into::into).collect())
+        })
+    }
+
+    /// Convert up to `limit` subscription lists of the legacy layout. Returns the number of converted lists
+    pub(crate) fn internal_migrate_subscriptions(&mut self, limit: u64) -> u64 {
+        let account_ids: Vec<AccountId> = self.legacy_subscriptions.keys().take(limit as usize).collect();
+        for account_id in account_ids.iter() {
+            let subscription_list = self.internal_subscriptions_of(account_id).unwrap_or_default();
+            self.internal_save_subscriptions(account_id, &subscription_list);
+        }
+        account_ids.len() as u64
+    }
+
+    /// Save the subscriptions of the account in the current layout dropping the legacy record if any.
+    /// The empty list is removed, so deleting all of the subscriptions releases all of their storage
+    pub(crate) fn internal_save_subscriptions(
+        &mut self,
+        account_id: &AccountId,
+        subscription_list: &Vec<subscriptions::UserSubscription>,
+    ) {
+        if subscription_list.is_empty() {
+            self.subscriptions.remove(account_id);
+        } else {
+            self.subscriptions.insert(account_id, subscription_list);
+        }
+        self.legacy_subscriptions.remove(account_id);
+    }
+
+    /// Charge the storage growth since `initial_storage_usage` from the attached deposit or refund the released storage.
+    /// The charged amount is kept in `storage_deposit` of the subscription
+    pub(crate) fn internal_settle_subscription_storage(
+        &mut self,
+        account_id: &AccountId,
+        id: SubscriptionId,
+        initial_storage_usage: StorageUsage,
+    ) {
+        let storage_usage = env::storage_usage();
+        let mut refund = env::attached_deposit();
+        self.internal_update_subscription(account_id, id, |subscription| {
+            if storage_usage >= initial_storage_usage {
+                let cost = Balance::from(storage_usage - initial_storage_usage) * env::storage_byte_cost();
+                assert!(
+                    refund >= cost,
+                    "Attach at least {} yoctoNEAR to cover the subscription storage",
+                    cost,
+                );
+                refund -= cost;
+                subscription.storage_deposit = U128(subscription.storage_deposit.0 + cost);
+            } else {
+                let released = Balance::from(initial_storage_usage - storage_usage) * env::storage_byte_cost();
+                let released = std::cmp::min(released, subscription.storage_deposit.0);
+                refund += released;
+                subscription.storage_deposit = U128(subscription.storage_deposit.0 - released);
             }
+        });
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
         }
     }
 }
@@ -0,0 +1,85 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use crate::*;
+
+/// Basis points denominator used for referral bonus rates (10_000 bps = 100%)
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Defines where referral bonuses are taken from
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BonusSource {
+    /// Bonus tokens are printed on top of the total supply
+    Mint,
+    /// Bonus tokens are transferred from the owner's account
+    Reserve,
+}
+
+/// Owner-defined settings of the referral program
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferralConfig {
+    /// Bonus credited to the referrer, in basis points of the purchased amount
+    pub referrer_bonus_bps: u16,
+    /// Bonus credited to the buyer, in basis points of the purchased amount
+    pub buyer_bonus_bps: u16,
+    /// Where the bonus tokens come from
+    pub source: BonusSource,
+    /// Maximum amount of bonus tokens (referrer and buyer bonuses together) issued through one referrer
+    pub max_bonus_per_referrer: U128,
+}
+
+impl Default for ReferralConfig {
+    /// Referral program is disabled until the owner sets non-zero rates
+    fn default() -> Self {
+        Self {
+            referrer_bonus_bps: 0,
+            buyer_bonus_bps: 0,
+            source: BonusSource::Reserve,
+            max_bonus_per_referrer: U128(0),
+        }
+    }
+}
+
+impl ReferralConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            u128::from(self.referrer_bonus_bps) <= BPS_DENOMINATOR
+                && u128::from(self.buyer_bonus_bps) <= BPS_DENOMINATOR,
+            "Bonus rate can't exceed {} bps",
+            BPS_DENOMINATOR,
+        );
+    }
+}
+
+/// Referral statistics collected for a referrer
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferralStats {
+    /// Number of purchases made with this referrer
+    pub referred_purchases: u64,
+    /// Total amount of tokens bought by referred accounts
+    pub referred_amount: U128,
+    /// Bonus tokens credited to the referrer
+    pub referrer_bonus: U128,
+    /// Bonus tokens credited to the referred buyers
+    pub buyer_bonus: U128,
+}
+
+impl Default for ReferralStats {
+    fn default() -> Self {
+        Self {
+            referred_purchases: 0,
+            referred_amount: U128(0),
+            referrer_bonus: U128(0),
+            buyer_bonus: U128(0),
+        }
+    }
+}
+
+impl ReferralStats {
+    /// Total amount of bonus tokens issued through the referrer so far
+    pub fn total_bonus(&self) -> Balance {
+        self.referrer_bonus.0 + self.buyer_bonus.0
+    }
+}
@@ -0,0 +1,78 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Gas;
+use crate::*;
+
+/// Minimal gas price (in yoctoNEAR) of the network, the relayed contract calls can't be charged at a lower price
+pub const MIN_GAS_PRICE: Balance = 100_000_000;
+/// Gas of the `on_contract_call_delivered` callback, charged along with the relayed call
+pub const DELIVERY_CALLBACK_GAS: Gas = 10_000_000_000_000;
+/// Maximum gas the relayed contract call can be prepaid with
+pub const MAX_RELAY_GAS: Gas = 100_000_000_000_000;
+/// Maximum length of the event payload passed as the arguments of the relayed call
+pub const MAX_RELAY_PAYLOAD_LEN: usize = 8192;
+
+/// NEAR prepaid by the account for the gas of the relayed contract calls
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasBudget {
+    /// Attached NEAR, can be withdrawn
+    pub deposited: U128,
+    /// NEAR value of the FT tokens converted at the exchange price, can't be withdrawn
+    pub converted: U128,
+}
+
+impl Default for GasBudget {
+    fn default() -> Self {
+        Self {
+            deposited: U128(0),
+            converted: U128(0),
+        }
+    }
+}
+
+impl GasBudget {
+    pub fn total(&self) -> Balance {
+        self.deposited.0 + self.converted.0
+    }
+
+    /// Spend the converted part first. Returns the spent amount of the deposited part
+    pub fn spend(&mut self, cost: Balance) -> Balance {
+        assert!(
+            self.total() >= cost,
+            "Not enough gas budget, {} yoctoNEAR is required",
+            cost,
+        );
+        let from_converted = std::cmp::min(self.converted.0, cost);
+        self.converted = U128(self.converted.0 - from_converted);
+        self.deposited = U128(self.deposited.0 - (cost - from_converted));
+        cost - from_converted
+    }
+}
+
+/// Outcomes of the relayed contract calls of the subscription
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeliveryResults {
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Block timestamp (in nanoseconds) of the last outcome
+    pub last_delivered_at: U64,
+    pub last_succeeded: bool,
+}
+
+impl Default for DeliveryResults {
+    fn default() -> Self {
+        Self {
+            succeeded: 0,
+            failed: 0,
+            last_delivered_at: U64(0),
+            last_succeeded: false,
+        }
+    }
+}
+
+/// Gas cost (in yoctoNEAR) of the relayed call prepaid with `gas` at `gas_price`
+pub fn relay_cost(gas: Gas, gas_price: Balance) -> Balance {
+    (gas + DELIVERY_CALLBACK_GAS) as Balance * gas_price
+}
@@ -0,0 +1,11 @@
+/// Maximum number of accounts in `...IdIn` triggers
+pub const MAX_ACCOUNT_LIST_LEN: usize = 64;
+
+/// Check the account is a sub-account (of any depth) of the `*.parent` suffix
+pub fn account_matches_suffix(suffix: &str, account_id: &str) -> bool {
+    match suffix.strip_prefix('*') {
+        Some(dot_parent) => account_id.len() > dot_parent.len() && account_id.ends_with(dot_parent),
+        None => false,
+    }
+}
+
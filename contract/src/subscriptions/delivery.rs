@@ -0,0 +1,96 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
+use near_sdk::json_types::{Base64VecU8, ValidAccountId};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use super::{Endpoint, SubscriptionError};
+
+/// Maximum number of delivery targets of a subscription
+pub const MAX_DELIVERY_TARGETS: usize = 4;
+/// Length of `Delivery::Email::address_hash`
+pub const EMAIL_ADDRESS_HASH_LEN: usize = 32;
+/// Maximum length of `Delivery::Websocket::channel`
+pub const MAX_WEBSOCKET_CHANNEL_LEN: usize = 64;
+/// Maximum length of `Delivery::ContractCall::method`
+pub const MAX_METHOD_NAME_LEN: usize = 256;
+
+/// Channel the dispatcher delivers the notification through
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "type")]
+pub enum Delivery {
+    /// HTTP POST to the URL, see `Endpoint`
+    Webhook { url: Endpoint },
+    /// Message from the bot to the Telegram chat
+    Telegram { chat_id: i64 },
+    /// Email to the address registered with the dispatcher. The address itself is never stored on-chain
+    Email { address_hash: Base64VecU8 },
+    /// Message to the websocket clients listening to the channel
+    Websocket { channel: String },
+    /// Function call of the contract method with the notification as the arguments
+    ContractCall { receiver_id: ValidAccountId, method: String },
+}
+
+impl Delivery {
+    /// Check the target the dispatcher can deliver to. Webhook URLs are normalized with `Endpoint::normalize`
+    pub fn normalize(self, allow_localhost: bool, current_key_version: Option<u64>) -> Result<Delivery, SubscriptionError> {
+        match self {
+            Delivery::Webhook { url } => Ok(Delivery::Webhook { url: url.normalize(allow_localhost, current_key_version)? }),
+            Delivery::Telegram { chat_id } => {
+                if chat_id == 0 {
+                    return Err(SubscriptionError::InvalidTelegramChatId);
+                }
+                Ok(Delivery::Telegram { chat_id })
+            }
+            Delivery::Email { address_hash } => {
+                if address_hash.0.len() != EMAIL_ADDRESS_HASH_LEN {
+                    return Err(SubscriptionError::InvalidEmailAddressHash);
+                }
+                Ok(Delivery::Email { address_hash })
+            }
+            Delivery::Websocket { channel } => {
+                if channel.is_empty()
+                    || channel.len() > MAX_WEBSOCKET_CHANNEL_LEN
+                    || !channel.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:".contains(c))
+                {
+                    return Err(SubscriptionError::InvalidWebsocketChannel);
+                }
+                Ok(Delivery::Websocket { channel })
+            }
+            Delivery::ContractCall { receiver_id, method } => {
+                // Relayed calls are signed by the owner, so they must never reach the owner methods of this contract
+                if receiver_id.as_ref() == &env::current_account_id()
+                    || method.is_empty()
+                    || method.len() > MAX_METHOD_NAME_LEN
+                    || method.starts_with(|c: char| c.is_ascii_digit())
+                    || !method.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    return Err(SubscriptionError::InvalidContractCall);
+                }
+                Ok(Delivery::ContractCall { receiver_id, method })
+            }
+        }
+    }
+}
+
+/// Normalize the delivery targets listed in the failover order
+pub fn normalize_delivery(
+    targets: Vec<Delivery>,
+    allow_localhost: bool,
+    current_key_version: Option<u64>,
+) -> Result<Vec<Delivery>, SubscriptionError> {
+    if targets.is_empty() {
+        return Err(SubscriptionError::EmptyDelivery);
+    }
+    if targets.len() > MAX_DELIVERY_TARGETS {
+        return Err(SubscriptionError::TooManyDeliveryTargets);
+    }
+    let mut normalized: Vec<Delivery> = Vec::with_capacity(targets.len());
+    for target in targets {
+        let target = target.normalize(allow_localhost, current_key_version)?;
+        if normalized.contains(&target) {
+            return Err(SubscriptionError::DuplicateDeliveryTarget);
+        }
+        normalized.push(target);
+    }
+    Ok(normalized)
+}
@@ -0,0 +1,221 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use super::SubscriptionError;
+
+/// Maximum length of the subscription endpoint
+pub const MAX_ENDPOINT_LEN: usize = 512;
+/// Length of the X25519 public key of the dispatcher
+pub const DISPATCHER_KEY_LEN: usize = 32;
+/// Length of the XSalsa20-Poly1305 nonce of the encrypted endpoint
+pub const ENCRYPTED_ENDPOINT_NONCE_LEN: usize = 24;
+/// Maximum length of the encrypted endpoint: the endpoint itself and the 16 bytes authentication tag
+pub const MAX_ENCRYPTED_ENDPOINT_LEN: usize = MAX_ENDPOINT_LEN + 16;
+
+/// Webhook address of the subscription. Accepts either a plain URL string or an encrypted URL object in JSON
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum Endpoint {
+    /// Webhook URL in the canonical form, see `normalize_endpoint`
+    Plain(String),
+    /// Webhook URL only the dispatcher can read
+    Encrypted(EncryptedEndpoint),
+}
+
+/// Webhook URL sealed with `crypto_box` (X25519, XSalsa20-Poly1305) for the dispatcher key of `key_version`
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EncryptedEndpoint {
+    pub ciphertext: Base64VecU8,
+    pub nonce: Base64VecU8,
+    pub key_version: u64,
+}
+
+/// Encryption public key of the dispatcher published by the owner
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DispatcherKey {
+    /// Version of the key, increases with every rotation
+    pub version: u64,
+    pub public_key: Base64VecU8,
+    /// Block timestamp (in nanoseconds) of the key publication
+    pub published_at: U64,
+}
+
+impl Endpoint {
+    /// Normalize the plain URL or check the encrypted one is sealed for the current dispatcher key.
+    /// The contract can't decrypt the endpoint, so only its size is checked
+    pub fn normalize(self, allow_localhost: bool, current_key_version: Option<u64>) -> Result<Endpoint, SubscriptionError> {
+        match self {
+            Endpoint::Plain(url) => normalize_endpoint(&url, allow_localhost).map(Endpoint::Plain),
+            Endpoint::Encrypted(encrypted) => {
+                if encrypted.ciphertext.0.is_empty()
+                    || encrypted.ciphertext.0.len() > MAX_ENCRYPTED_ENDPOINT_LEN
+                    || encrypted.nonce.0.len() != ENCRYPTED_ENDPOINT_NONCE_LEN
+                {
+                    return Err(SubscriptionError::InvalidEncryptedEndpoint);
+                }
+                if current_key_version != Some(encrypted.key_version) {
+                    return Err(SubscriptionError::StaleDispatcherKey);
+                }
+                Ok(Endpoint::Encrypted(encrypted))
+            }
+        }
+    }
+}
+
+/// Host allowed over `http://` when the owner enables localhost endpoints for development.
+/// Its subdomains resolve to the loopback too (RFC 6761) and are treated the same way
+pub const LOCALHOST: &str = "localhost";
+
+/// Parse the webhook URL and return its canonical form.
+///
+/// Only `https://` URLs are accepted, `http://localhost` and `http://*.localhost` are accepted too if `allow_localhost` is set.
+/// URLs with credentials (`user:password@`) or a private, loopback or link-local IP literal host are rejected.
+/// The canonical form has the scheme and host in lowercase, no default port, no fragment
+/// and `/` for the empty path, e.g. `HTTPS://Example.com:443?a=1#b` becomes `https://example.com/?a=1`
+pub fn normalize_endpoint(endpoint: &str, allow_localhost: bool) -> Result<String, SubscriptionError> {
+    if endpoint.len() > MAX_ENDPOINT_LEN {
+        return Err(SubscriptionError::EndpointTooLong);
+    }
+    if !endpoint.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(SubscriptionError::InvalidEndpoint);
+    }
+    let scheme_end = endpoint.find("://").ok_or(SubscriptionError::InvalidEndpoint)?;
+    let scheme = endpoint[..scheme_end].to_ascii_lowercase();
+    let rest = &endpoint[scheme_end + 3..];
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if authority.contains('@') {
+        return Err(SubscriptionError::EndpointWithCredentials);
+    }
+    let (host, port) = parse_authority(authority)?;
+    let is_localhost = host == LOCALHOST || host.ends_with(&format!(".{}", LOCALHOST));
+    if is_localhost && !allow_localhost {
+        return Err(SubscriptionError::PrivateEndpointHost);
+    }
+    let default_port = match scheme.as_str() {
+        "https" => 443,
+        "http" if is_localhost => 80,
+        _ => return Err(SubscriptionError::InsecureEndpoint),
+    };
+    let port = match port {
+        Some(port) if port != default_port => format!(":{}", port),
+        _ => String::new(),
+    };
+    let path = path.split('#').next().unwrap_or("");
+    let slash = if path.starts_with('/') { "" } else { "/" };
+    Ok(format!("{}://{}{}{}{}", scheme, host, port, slash, path))
+}
+
+/// Split the authority into the canonical host and the port
+fn parse_authority(authority: &str) -> Result<(String, Option<u16>), SubscriptionError> {
+    let (host, port) = if let Some(ipv6) = authority.strip_prefix('[') {
+        let host_end = ipv6.find(']').ok_or(SubscriptionError::InvalidEndpoint)?;
+        let ip: Ipv6Addr = ipv6[..host_end].parse().map_err(|_| SubscriptionError::InvalidEndpoint)?;
+        if is_private_ipv6(&ip) {
+            return Err(SubscriptionError::PrivateEndpointHost);
+        }
+        let port = match &ipv6[host_end + 1..] {
+            "" => None,
+            port => Some(port.strip_prefix(':').ok_or(SubscriptionError::InvalidEndpoint)?),
+        };
+        (format!("[{}]", ip), port)
+    } else {
+        let (host, port) = match authority.rfind(':') {
+            Some(port_start) => (&authority[..port_start], Some(&authority[port_start + 1..])),
+            None => (authority, None),
+        };
+        (parse_host(host)?, port)
+    };
+    let port = match port {
+        Some(port) => {
+            if port.is_empty() || !port.chars().all(|c| c.is_ascii_digit()) {
+                return Err(SubscriptionError::InvalidEndpoint);
+            }
+            match port.parse::<u16>() {
+                Ok(port) if port > 0 => Some(port),
+                _ => return Err(SubscriptionError::InvalidEndpoint),
+            }
+        }
+        None => None,
+    };
+    Ok((host, port))
+}
+
+/// Validate the domain name or IPv4 literal and return it in lowercase
+fn parse_host(host: &str) -> Result<String, SubscriptionError> {
+    let host = host.strip_suffix('.').unwrap_or(host).to_ascii_lowercase();
+    // Numeric hosts must be dotted-decimal IPv4, other forms like `0x7f.1` or `2130706433` are rejected
+    // A host is numeric if its last label is a number, URL parsers read it as IPv4 in decimal, octal or hex
+    if matches!(host.rsplit('.').next(), Some(label) if is_numeric_label(label)) {
+        let is_decimal_octet = |octet: &str| {
+            !octet.is_empty() && octet.chars().all(|c| c.is_ascii_digit()) && (octet == "0" || !octet.starts_with('0'))
+        };
+        if !host.split('.').all(is_decimal_octet) {
+            return Err(SubscriptionError::InvalidEndpoint);
+        }
+        let ip: Ipv4Addr = host.parse().map_err(|_| SubscriptionError::InvalidEndpoint)?;
+        if is_private_ipv4(&ip) {
+            return Err(SubscriptionError::PrivateEndpointHost);
+        }
+        return Ok(ip.to_string());
+    }
+    let is_valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if !host.split('.').all(is_valid_label) {
+        return Err(SubscriptionError::InvalidEndpoint);
+    }
+    Ok(host)
+}
+
+/// Check the label is a decimal, octal (`0` prefixed) or hex (`0x` prefixed) number
+fn is_numeric_label(label: &str) -> bool {
+    match label.strip_prefix("0x") {
+        Some(hex) => hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !label.is_empty() && label.chars().all(|c| c.is_ascii_digit()),
+    }
+}
+
+fn is_private_ipv4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || octets[0] == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (octets[0] == 100 && octets[1] & 0xc0 == 64)
+}
+
+fn is_private_ipv6(ip: &Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    let is_ipv4_mapped = segments[..5].iter().all(|segment| *segment == 0) && segments[5] == 0xffff;
+    // IPv4-compatible, ::/96, including `::` and `::1`
+    let is_ipv4_compatible = segments[..6].iter().all(|segment| *segment == 0);
+    // NAT64, 64:ff9b::/96 and the local-use 64:ff9b:1::/48, translated to any IPv4 address by the network
+    let is_nat64 = segments[0] == 0x64
+        && segments[1] == 0xff9b
+        && (segments[2] == 1 || segments[2..6].iter().all(|segment| *segment == 0));
+    is_ipv4_compatible
+        || is_nat64
+        // Unique local, fc00::/7
+        || segments[0] & 0xfe00 == 0xfc00
+        // Link-local, fe80::/10
+        || segments[0] & 0xffc0 == 0xfe80
+        || (is_ipv4_mapped
+            && is_private_ipv4(&Ipv4Addr::new(
+                (segments[6] >> 8) as u8,
+                segments[6] as u8,
+                (segments[7] >> 8) as u8,
+                segments[7] as u8,
+            )))
+}
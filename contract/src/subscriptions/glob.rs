@@ -0,0 +1,143 @@
+/// Maximum length of a function name pattern
+pub const MAX_FUNCTION_NAME_PATTERN_LEN: usize = 128;
+
+/// Token of a parsed glob pattern
+#[derive(Clone, PartialEq, Debug)]
+enum GlobToken {
+    /// Exact character
+    Literal(char),
+    /// `?`, any single character
+    AnyChar,
+    /// `*`, any sequence of characters including an empty one
+    AnySequence,
+    /// `[...]`, a character from the set (or not from the set if `negated`)
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+/// Parse the glob pattern, see `glob_matches` for the syntax
+fn parse_glob(pattern: &str) -> Result<Vec<GlobToken>, String> {
+    if pattern.is_empty() {
+        return Err("Function name pattern can't be empty".to_string());
+    }
+    if pattern.len() > MAX_FUNCTION_NAME_PATTERN_LEN {
+        return Err(format!(
+            "Function name pattern can't be longer than {} characters",
+            MAX_FUNCTION_NAME_PATTERN_LEN,
+        ));
+    }
+    if let Some(c) = pattern.chars().find(|c| !c.is_ascii_graphic()) {
+        return Err(format!("Function name pattern has an unsupported character {:?}", c));
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' => GlobToken::AnySequence,
+            '?' => GlobToken::AnyChar,
+            '\\' => GlobToken::Literal(
+                chars
+                    .next()
+                    .ok_or_else(|| "Function name pattern ends with an unfinished escape".to_string())?,
+            ),
+            '[' => {
+                let negated = chars.peek() == Some(&'!');
+                if negated {
+                    chars.next();
+                }
+                let mut ranges = Vec::new();
+                loop {
+                    let start = match chars.next() {
+                        None => return Err("Function name pattern has an unclosed `[`".to_string()),
+                        Some(']') if !ranges.is_empty() => break,
+                        Some(start) => start,
+                    };
+                    let end = if chars.peek() == Some(&'-') {
+                        chars.next();
+                        match chars.next() {
+                            None => return Err("Function name pattern has an unclosed `[`".to_string()),
+                            // `-` right before `]` is a literal
+                            Some(']') => {
+                                ranges.push((start, start));
+                                ranges.push(('-', '-'));
+                                break;
+                            }
+                            Some(end) => end,
+                        }
+                    } else {
+                        start
+                    };
+                    if start > end {
+                        return Err(format!("Function name pattern has an invalid range {}-{}", start, end));
+                    }
+                    ranges.push((start, end));
+                }
+                GlobToken::Class { negated, ranges }
+            }
+            c => GlobToken::Literal(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn token_matches(token: &GlobToken, c: char) -> bool {
+    match token {
+        GlobToken::Literal(literal) => *literal == c,
+        GlobToken::AnyChar => true,
+        GlobToken::AnySequence => false,
+        GlobToken::Class { negated, ranges } => {
+            ranges.iter().any(|(start, end)| *start <= c && c <= *end) != *negated
+        }
+    }
+}
+
+/// Check the function name pattern is valid
+pub fn validate_glob(pattern: &str) -> Result<(), String> {
+    parse_glob(pattern).map(|_| ())
+}
+
+/// Check the function name matches the glob pattern of `FunctionCallTrigger::FunctionNameLike`.
+/// Invalid patterns never match.
+///
+/// Syntax:
+///  - `*` matches any sequence of characters, including an empty one
+///  - `?` matches any single character
+///  - `[abc]`, `[a-z]` match a character from the set, `[!a-z]` matches a character not in the set.
+///    `]` right after `[` or `[!` and `-` right before `]` are literals
+///  - `\` escapes the next character
+///  - any other printable ASCII character matches itself
+///
+/// Patterns are limited to `MAX_FUNCTION_NAME_PATTERN_LEN` characters.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let tokens = match parse_glob(pattern) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut token_index, mut name_index) = (0, 0);
+    // Position of the last `*` and the name position it has been tried with
+    let mut backtrack: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        match tokens.get(token_index) {
+            Some(GlobToken::AnySequence) => {
+                backtrack = Some((token_index, name_index));
+                token_index += 1;
+            }
+            Some(token) if token_matches(token, name[name_index]) => {
+                token_index += 1;
+                name_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, star_name_index)) => {
+                    backtrack = Some((star_index, star_name_index + 1));
+                    token_index = star_index + 1;
+                    name_index = star_name_index + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[token_index..].iter().all(|token| *token == GlobToken::AnySequence)
+}
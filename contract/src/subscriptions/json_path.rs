@@ -0,0 +1,105 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use near_sdk::serde_json::{self, Value};
+
+/// Maximum length of a JSON path
+pub const MAX_JSON_PATH_LEN: usize = 256;
+/// Maximum number of segments in a JSON path
+pub const MAX_JSON_PATH_SEGMENTS: usize = 16;
+/// Maximum length of a serialized JSON value to compare with
+pub const MAX_JSON_VALUE_LEN: usize = 512;
+
+/// Segment of a parsed JSON path
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonPathSegment {
+    /// Object member, `.key`
+    Key(String),
+    /// Array element, `[index]`
+    Index(usize),
+}
+
+/// Parse the JSON path. Supported syntax is `$` followed by any number of `.key` and `[index]` segments,
+/// where `key` consists of ASCII alphanumerics, `_` and `-`. E.g. `$.receiver_id` or `$.actions[0].amount`
+pub fn parse_json_path(path: &str) -> Result<Vec<JsonPathSegment>, String> {
+    if path.len() > MAX_JSON_PATH_LEN {
+        return Err(format!("JSON path can't be longer than {} characters", MAX_JSON_PATH_LEN));
+    }
+    let mut rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| "JSON path must start with `$`".to_string())?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let key_len = after_dot
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(after_dot.len());
+            if key_len == 0 {
+                return Err(format!("JSON path `{}` has an empty key", path));
+            }
+            segments.push(JsonPathSegment::Key(after_dot[..key_len].to_string()));
+            rest = &after_dot[key_len..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let index_len = after_bracket
+                .find(']')
+                .ok_or_else(|| format!("JSON path `{}` has an unclosed `[`", path))?;
+            let index = after_bracket[..index_len]
+                .parse::<usize>()
+                .map_err(|_| format!("JSON path `{}` has an invalid array index", path))?;
+            segments.push(JsonPathSegment::Index(index));
+            rest = &after_bracket[index_len + 1..];
+        } else {
+            return Err(format!("JSON path `{}` has an unexpected character", path));
+        }
+        if segments.len() > MAX_JSON_PATH_SEGMENTS {
+            return Err(format!("JSON path can't have more than {} segments", MAX_JSON_PATH_SEGMENTS));
+        }
+    }
+    Ok(segments)
+}
+
+/// Find the value at the parsed JSON path
+pub fn json_path_lookup<'a>(value: &'a Value, segments: &[JsonPathSegment]) -> Option<&'a Value> {
+    segments.iter().try_fold(value, |value, segment| match segment {
+        JsonPathSegment::Key(key) => value.get(key.as_str()),
+        JsonPathSegment::Index(index) => value.get(*index),
+    })
+}
+
+/// Arbitrary JSON value. Stored as its serialized form
+#[derive(BorshDeserialize, BorshSerialize, Clone, PartialEq, Debug)]
+pub struct JsonValue(String);
+
+impl JsonValue {
+    pub fn value(&self) -> Value {
+        serde_json::from_str(&self.0).expect("JsonValue is always a valid JSON")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<Value> for JsonValue {
+    fn from(value: Value) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json_value = JsonValue::from(Value::deserialize(deserializer)?);
+        if json_value.0.len() > MAX_JSON_VALUE_LEN {
+            return Err(de::Error::custom(format!(
+                "JSON value can't be longer than {} characters",
+                MAX_JSON_VALUE_LEN,
+            )));
+        }
+        Ok(json_value)
+    }
+}
@@ -1,44 +1,437 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, Base58PublicKey, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use crate::*;
 
+pub use account_pattern::*;
+pub use delivery::*;
+pub use endpoint::*;
+pub use glob::*;
+pub use json_path::*;
+pub use trigger_expr::*;
+pub use validation::*;
+
+mod account_pattern;
+mod delivery;
+mod endpoint;
+mod glob;
+mod json_path;
+mod trigger_expr;
+mod validation;
+
+/// Time (in nanoseconds) after which the operator can prune undelivered one-shot watches
+pub const ONE_SHOT_WATCH_TTL: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Prefix of the NEP-297 event logs `FtTransfer`, `NftTransfer` and `ContractLog` events are read from
+pub const EVENT_JSON_LOG_PREFIX: &str = "EVENT_JSON:";
+/// Maximum length of the substring in `LogTrigger::LogContains`
+pub const MAX_LOG_SUBSTRING_LEN: usize = 256;
+/// Maximum length of `LogTrigger::Nep297Standard` and `LogTrigger::Nep297Event`
+pub const MAX_NEP297_FIELD_LEN: usize = 64;
+
+/// Share (in basis points) of the storage deposit of the pruned subscription paid to the caller of `prune_expired`.
+/// It's taken from the deposit rather than the treasury, so pruning can't be farmed with short-living subscriptions
+pub const KEEPER_REWARD_BPS: u128 = 1_000;
+
+/// Outcome of the `prune_expired` call
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PruneResult {
+    /// Number of the deleted subscriptions
+    pub pruned: u64,
+    /// Index to continue pruning from, `None` once the last account has been checked
+    pub next_index: Option<u64>,
+}
+
+/// Parse the NEP-297 event log. Returns `None` for regular logs
+pub fn parse_nep297_log(log: &str) -> Option<near_sdk::serde_json::Value> {
+    let event = log.strip_prefix(EVENT_JSON_LOG_PREFIX)?;
+    near_sdk::serde_json::from_str(event.trim_start()).ok()
+}
+
+/// Id of the subscription, ids of the deleted subscriptions are never reused by the account
+pub type SubscriptionId = u32;
+
 /// Represent the record what user is subscribed for
-#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize)]
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub struct UserSubscription {
-    /// Generated uuid
-    pub id: u8,
+    /// Generated id, unique among all subscriptions the account has ever had
+    pub id: SubscriptionId,
     /// Defines if the subscription is action
     pub enabled: bool,
-    /// Endcoded webhook address
-    pub endpoint: String,
+    /// Targets the notification is delivered to, the next one is tried if the delivery fails
+    pub delivery: Vec<Delivery>,
     /// The event itself
     pub event: Event,
+    /// Block timestamp (in nanoseconds) of the subscription creation
+    pub created_at: U64,
+    /// Finality of the blocks the dispatcher checks the event in
+    pub finality: Finality,
+    /// Number of blocks the dispatcher waits after the event before the notification
+    pub min_confirmations: Option<u32>,
+    /// sha256 of the HMAC secret the dispatcher signs the notifications with, see `validate_secret_hash`
+    pub secret_hash: Option<Base64VecU8>,
+    /// Block timestamp (in nanoseconds) the subscription expires at
+    pub expires_at: Option<U64>,
+    /// Number of deliveries after which the subscription expires
+    pub max_deliveries: Option<u64>,
+    /// Number of deliveries reported so far
+    pub deliveries: u64,
+    /// yoctoNEAR attached for the storage of the subscription, refunded once it's deleted
+    pub storage_deposit: U128,
+}
+
+/// Record of the subscription of the first deployed layout,
+/// converted to `UserSubscription` with the `Webhook` delivery on the first access
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LegacyUserSubscription {
+    pub id: u8,
+    pub enabled: bool,
+    pub endpoint: String,
+    pub event: LegacyEvent,
+}
+
+impl From<LegacyUserSubscription> for UserSubscription {
+    fn from(legacy: LegacyUserSubscription) -> Self {
+        UserSubscription {
+            id: legacy.id.into(),
+            enabled: legacy.enabled,
+            delivery: vec![Delivery::Webhook { url: Endpoint::Plain(legacy.endpoint) }],
+            event: legacy.event.into(),
+            // Creation time wasn't recorded
+            created_at: U64(0),
+            finality: Finality::Final,
+            min_confirmations: None,
+            secret_hash: None,
+            expires_at: None,
+            max_deliveries: None,
+            deliveries: 0,
+            storage_deposit: U128(0),
+        }
+    }
+}
+
+impl UserSubscription {
+    /// Expired subscriptions aren't delivered anymore and can be deleted by anyone with `prune_expired`
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at.0)
+            || matches!(self.max_deliveries, Some(max_deliveries) if self.deliveries >= max_deliveries)
+    }
+
+    /// One-shot subscriptions are deleted once the notification has been delivered
+    pub fn is_one_shot(&self) -> bool {
+        matches!(self.event, Event::WatchTransaction(_) | Event::WatchReceipt(_))
+    }
+}
+
+/// Maximum value of `UserSubscription::min_confirmations`
+pub const MAX_MIN_CONFIRMATIONS: u32 = 100;
+
+/// Finality of the blocks the dispatcher checks the event in
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Finality {
+    /// Blocks are checked as soon as they are produced
+    Optimistic,
+    /// Blocks with Doomslug finality
+    DoomSlug,
+    /// Final blocks only
+    Final,
 }
 
-/// Represents the event which should be checked for a user
-#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize)]
+/// Represents the event which should be checked for a user.
+/// Triggers of the event are combined with a `TriggerExpr`, a flat list of triggers means all of them must match
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde", tag = "kind", content = "triggers")]
 pub enum Event {
     /// ExecutionOutcome for a Receipt with Transfer action(s)
-    ReceiptTransferResult(Vec<TransferTrigger>),
+    ReceiptTransferResult(TriggerExpr<TransferTrigger>),
     /// ExecutionOutcome for a Receipt with FunctionCall action(s)
+    ReceiptFunctionCallResult(TriggerExpr<FunctionCallTrigger>),
+    /// ExecutionOutcome for a Receipt with CreateAccount action
+    ReceiptCreateAccountResult(TriggerExpr<CreateAccountTrigger>),
+    /// ExecutionOutcome for a Receipt with DeployContract action
+    ReceiptDeployContractResult(TriggerExpr<DeployContractTrigger>),
+    /// ExecutionOutcome for a Receipt with Stake action
+    ReceiptStakeResult(TriggerExpr<StakeTrigger>),
+    /// ExecutionOutcome for a Receipt with AddKey action(s)
+    ReceiptAddKeyResult(TriggerExpr<AddKeyTrigger>),
+    /// ExecutionOutcome for a Receipt with DeleteKey action(s)
+    ReceiptDeleteKeyResult(TriggerExpr<DeleteKeyTrigger>),
+    /// ExecutionOutcome for a Receipt with DeleteAccount action
+    ReceiptDeleteAccountResult(TriggerExpr<DeleteAccountTrigger>),
+    /// NEP-141 `ft_transfer` event emitted as a NEP-297 `EVENT_JSON` log
+    FtTransfer(TriggerExpr<FtTransferTrigger>),
+    /// NEP-171 `nft_transfer` event emitted as a NEP-297 `EVENT_JSON` log
+    NftTransfer(TriggerExpr<NftTransferTrigger>),
+    /// Change of the account balance. Edge-triggered: thresholds fire only when the balance crosses them.
+    /// The root `All` must have the `AccountId` trigger, so the subscription can't watch every account
+    AccountBalance(TriggerExpr<BalanceTrigger>),
+    /// Change of the contract storage, e.g. an entry of a collection with a known prefix.
+    /// The root `All` must have the `AccountId` trigger, so the subscription can't watch every contract
+    ContractDataChange(TriggerExpr<DataChangeTrigger>),
+    /// Log emitted by a contract, including NEP-297 events
+    ContractLog(TriggerExpr<LogTrigger>),
+    /// Final outcome of a Transaction, matched once all of its receipts have been finalized
+    TransactionOutcome(TriggerExpr<TransactionTrigger>),
+    /// One-shot watch of the final outcome of the Transaction with the given hash
+    WatchTransaction(Base58CryptoHash),
+    /// One-shot watch of the ExecutionOutcome of the Receipt with the given id
+    WatchReceipt(Base58CryptoHash),
+}
+
+/// Event of the subscriptions stored before the trigger expressions were introduced.
+/// New trigger variants are only appended, so the stored triggers are read with the current trigger enums
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum LegacyEvent {
+    ReceiptTransferResult(Vec<TransferTrigger>),
     ReceiptFunctionCallResult(Vec<FunctionCallTrigger>),
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize)]
+impl From<LegacyEvent> for Event {
+    fn from(legacy: LegacyEvent) -> Self {
+        match legacy {
+            LegacyEvent::ReceiptTransferResult(triggers) => Event::ReceiptTransferResult(triggers.into()),
+            LegacyEvent::ReceiptFunctionCallResult(triggers) => Event::ReceiptFunctionCallResult(triggers.into()),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
 pub enum TransferTrigger {
     SignerId(ValidAccountId),
     ReceiverId(ValidAccountId),
     AmountEqualOrGreater(U128),
     AmountEqualOrLower(U128),
+    OutcomeStatus(OutcomeStatus),
+    /// Sub-account of the given account, e.g. `*.sweat`
+    ReceiverIdSuffix(String),
+    ReceiverIdIn(Vec<ValidAccountId>),
+    SignerIdIn(Vec<ValidAccountId>),
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize)]
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
 pub enum FunctionCallTrigger {
     ReceiverId(ValidAccountId),
     FunctionNameExact(String),
+    /// Glob pattern of the function name, see `glob_matches` for the syntax
     FunctionNameLike(String),
+    OutcomeStatus(OutcomeStatus),
+    PredecessorId(ValidAccountId),
+    SignerId(ValidAccountId),
+    AttachedDepositEqualOrGreater(U128),
+    AttachedDepositEqualOrLower(U128),
+    GasEqualOrGreater(U64),
+    /// The JSON arguments have the value at the given path equal to `equals`
+    ArgsJsonPath { path: String, equals: JsonValue },
+    /// The JSON arguments have any value at the given path
+    ArgsJsonPathExists(String),
+    /// Sub-account of the given account, e.g. `*.sweat`
+    ReceiverIdSuffix(String),
+    ReceiverIdIn(Vec<ValidAccountId>),
+    SignerIdIn(Vec<ValidAccountId>),
+}
+
+/// Maximum length of the error kind in `OutcomeStatus::FailureWithErrorKind`
+pub const MAX_ERROR_KIND_LEN: usize = 64;
+
+/// Status of the Receipt ExecutionOutcome
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OutcomeStatus {
+    /// The receipt has been executed successfully
+    Success,
+    /// The receipt execution has failed with any error
+    Failure,
+    /// The receipt execution has failed with the given error kind, e.g. `FunctionCallError`
+    FailureWithErrorKind(String),
+}
+
+impl OutcomeStatus {
+    /// Check the status against the outcome. `error_kind` is `None` for successful outcomes
+    pub fn matches(&self, error_kind: Option<&str>) -> bool {
+        match (self, error_kind) {
+            (OutcomeStatus::Success, None) => true,
+            (OutcomeStatus::Failure, Some(_)) => true,
+            (OutcomeStatus::FailureWithErrorKind(expected), Some(actual)) => expected == actual,
+            _ => false,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum FtTransferTrigger {
+    /// The NEP-141 contract emitting the event
+    TokenContractId(ValidAccountId),
+    OldOwnerId(ValidAccountId),
+    NewOwnerId(ValidAccountId),
+    AmountEqualOrGreater(U128),
+    AmountEqualOrLower(U128),
+}
+
+/// Maximum length of the token id in `NftTransferTrigger::TokenId`
+pub const MAX_TOKEN_ID_LEN: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum NftTransferTrigger {
+    /// The NEP-171 contract emitting the event
+    TokenContractId(ValidAccountId),
+    OldOwnerId(ValidAccountId),
+    NewOwnerId(ValidAccountId),
+    TokenId(String),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum BalanceTrigger {
+    /// The watched account
+    AccountId(ValidAccountId),
+    /// The balance drops from `>=` threshold to below it
+    FallsBelow(U128),
+    /// The balance grows from `<=` threshold to above it
+    RisesAbove(U128),
+}
+
+impl BalanceTrigger {
+    /// Check the balance change of the account. `AccountId` filters the account, thresholds must be crossed
+    pub fn matches(&self, account_id: &str, previous_balance: Balance, current_balance: Balance) -> bool {
+        match self {
+            BalanceTrigger::AccountId(expected) => expected.as_ref() == account_id,
+            _ => self.is_crossed(previous_balance, current_balance),
+        }
+    }
+
+    /// Check the threshold is crossed by the balance change. `AccountId` is not a threshold and never fires
+    pub fn is_crossed(&self, previous_balance: Balance, current_balance: Balance) -> bool {
+        match self {
+            BalanceTrigger::AccountId(_) => false,
+            BalanceTrigger::FallsBelow(threshold) => previous_balance >= threshold.0 && current_balance < threshold.0,
+            BalanceTrigger::RisesAbove(threshold) => previous_balance <= threshold.0 && current_balance > threshold.0,
+        }
+    }
+}
+
+/// Maximum length of the storage key prefix in `DataChangeTrigger::KeyPrefixBase64`
+pub const MAX_DATA_KEY_PREFIX_LEN: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum DataChangeTrigger {
+    /// The contract account whose storage is watched
+    AccountId(ValidAccountId),
+    /// Raw storage key starts with the prefix, e.g. `dA==` (`b"t"`) for the FT balances of this contract
+    KeyPrefixBase64(Base64VecU8),
+    ChangeKind(DataChangeKind),
+}
+
+impl DataChangeTrigger {
+    /// Check the storage change of the contract
+    pub fn matches(&self, account_id: &str, key: &[u8], change_kind: &DataChangeKind) -> bool {
+        match self {
+            DataChangeTrigger::AccountId(expected) => expected.as_ref() == account_id,
+            DataChangeTrigger::KeyPrefixBase64(prefix) => key.starts_with(&prefix.0),
+            DataChangeTrigger::ChangeKind(expected) => expected == change_kind,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum TransactionTrigger {
+    SignerId(ValidAccountId),
+    ReceiverId(ValidAccountId),
+    /// Overall status: `Success` if all of the receipts have succeeded
+    OutcomeStatus(OutcomeStatus),
+    /// Any receipt of the transaction has been executed on the account
+    TouchedAccountId(ValidAccountId),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum LogTrigger {
+    /// The contract emitting the log
+    EmitterId(ValidAccountId),
+    /// The log contains the substring
+    LogContains(String),
+    /// NEP-297 event `standard` field, e.g. `nep171`
+    Nep297Standard(String),
+    /// NEP-297 event `event` field, e.g. `nft_mint`
+    Nep297Event(String),
+    /// NEP-297 event has the value at the given path of its `data` field equal to `equals`
+    Nep297DataJsonPath { path: String, equals: JsonValue },
+}
+
+/// Kind of the contract storage change
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DataChangeKind {
+    /// The value has been written
+    Update,
+    /// The key has been removed
+    Delete,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum CreateAccountTrigger {
+    SignerId(ValidAccountId),
+    /// The account being created
+    ReceiverId(ValidAccountId),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum DeployContractTrigger {
+    SignerId(ValidAccountId),
+    /// The account the contract is deployed to
+    ReceiverId(ValidAccountId),
+    /// sha256 of the deployed code
+    CodeHash(Base58CryptoHash),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum StakeTrigger {
+    /// The staking account
+    ReceiverId(ValidAccountId),
+    /// The validator key
+    PublicKey(Base58PublicKey),
+    AmountEqualOrGreater(U128),
+    AmountEqualOrLower(U128),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum AddKeyTrigger {
+    SignerId(ValidAccountId),
+    /// The account the key is added to
+    ReceiverId(ValidAccountId),
+    PublicKey(Base58PublicKey),
+    /// Matches only keys with FullAccess permission
+    FullAccessOnly,
+    /// Matches FunctionCall access keys allowed to call the given contract
+    FunctionCallReceiverId(ValidAccountId),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum DeleteKeyTrigger {
+    SignerId(ValidAccountId),
+    /// The account the key is deleted from
+    ReceiverId(ValidAccountId),
+    PublicKey(Base58PublicKey),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", tag = "parameter", content = "value")]
+pub enum DeleteAccountTrigger {
+    SignerId(ValidAccountId),
+    /// The account being deleted
+    ReceiverId(ValidAccountId),
+    BeneficiaryId(ValidAccountId),
 }
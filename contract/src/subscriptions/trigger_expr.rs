@@ -0,0 +1,167 @@
+use std::io;
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Maximum nesting depth of a trigger expression (a flat list of triggers has depth 2)
+pub const MAX_TRIGGER_EXPR_DEPTH: u32 = 5;
+/// Maximum number of nodes (operators and triggers) in a trigger expression
+pub const MAX_TRIGGER_EXPR_NODES: u32 = 32;
+
+/// Boolean expression over triggers of one kind.
+///
+/// JSON representation:
+///  - `[expr, ...]` is `All` (the flat list of triggers used by existing clients)
+///  - `{"any": [expr, ...]}` is `Any`
+///  - `{"not": expr}` is `Not`
+///  - `{"parameter": ..., "value": ...}` is a single trigger
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(
+    crate = "near_sdk::serde",
+    from = "TriggerExprRepr<T>",
+    into = "TriggerExprRepr<T>",
+    bound = "T: Serialize + DeserializeOwned + Clone"
+)]
+pub enum TriggerExpr<T> {
+    /// Matches if all of the nested expressions match
+    All(Vec<TriggerExpr<T>>),
+    /// Matches if any of the nested expressions matches
+    Any(Vec<TriggerExpr<T>>),
+    /// Matches if the nested expression doesn't match
+    Not(Box<TriggerExpr<T>>),
+    /// Matches if the trigger matches
+    Leaf(T),
+}
+
+impl<T> TriggerExpr<T> {
+    /// Evaluate the expression checking every trigger with `leaf_matches`
+    pub fn matches<F: Fn(&T) -> bool>(&self, leaf_matches: &F) -> bool {
+        match self {
+            TriggerExpr::All(exprs) => exprs.iter().all(|expr| expr.matches(leaf_matches)),
+            TriggerExpr::Any(exprs) => exprs.iter().any(|expr| expr.matches(leaf_matches)),
+            TriggerExpr::Not(expr) => !expr.matches(leaf_matches),
+            TriggerExpr::Leaf(trigger) => leaf_matches(trigger),
+        }
+    }
+
+    /// Nesting depth of the expression, a single trigger has depth 1
+    pub fn depth(&self) -> u32 {
+        match self {
+            TriggerExpr::All(exprs) | TriggerExpr::Any(exprs) => {
+                1 + exprs.iter().map(|expr| expr.depth()).max().unwrap_or(0)
+            }
+            TriggerExpr::Not(expr) => 1 + expr.depth(),
+            TriggerExpr::Leaf(_) => 1,
+        }
+    }
+
+    /// Number of operators and triggers in the expression
+    pub fn node_count(&self) -> u32 {
+        match self {
+            TriggerExpr::All(exprs) | TriggerExpr::Any(exprs) => {
+                1 + exprs.iter().map(|expr| expr.node_count()).sum::<u32>()
+            }
+            TriggerExpr::Not(expr) => 1 + expr.node_count(),
+            TriggerExpr::Leaf(_) => 1,
+        }
+    }
+
+    /// Triggers which must match for the whole expression to match: the trigger itself or the triggers of the root `All`
+    pub fn conjuncts(&self) -> Vec<&T> {
+        match self {
+            TriggerExpr::All(exprs) => exprs.iter().flat_map(|expr| expr.conjuncts()).collect(),
+            TriggerExpr::Any(_) | TriggerExpr::Not(_) => vec![],
+            TriggerExpr::Leaf(trigger) => vec![trigger],
+        }
+    }
+
+    /// All triggers of the expression regardless of the operators
+    pub fn leaves(&self) -> Vec<&T> {
+        match self {
+            TriggerExpr::All(exprs) | TriggerExpr::Any(exprs) => {
+                exprs.iter().flat_map(|expr| expr.leaves()).collect()
+            }
+            TriggerExpr::Not(expr) => expr.leaves(),
+            TriggerExpr::Leaf(trigger) => vec![trigger],
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for TriggerExpr<T> {
+    /// A flat list of triggers means all of them must match
+    fn from(triggers: Vec<T>) -> Self {
+        TriggerExpr::All(triggers.into_iter().map(TriggerExpr::Leaf).collect())
+    }
+}
+
+// Borsh is implemented manually since the derived bounds of the recursive enum can't be resolved
+impl<T: BorshSerialize> BorshSerialize for TriggerExpr<T> {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            TriggerExpr::All(exprs) => {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(exprs, writer)
+            }
+            TriggerExpr::Any(exprs) => {
+                BorshSerialize::serialize(&1u8, writer)?;
+                BorshSerialize::serialize(exprs, writer)
+            }
+            TriggerExpr::Not(expr) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                BorshSerialize::serialize(expr, writer)
+            }
+            TriggerExpr::Leaf(trigger) => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                BorshSerialize::serialize(trigger, writer)
+            }
+        }
+    }
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for TriggerExpr<T> {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        match <u8 as BorshDeserialize>::deserialize(buf)? {
+            0 => Ok(TriggerExpr::All(BorshDeserialize::deserialize(buf)?)),
+            1 => Ok(TriggerExpr::Any(BorshDeserialize::deserialize(buf)?)),
+            2 => Ok(TriggerExpr::Not(Box::new(BorshDeserialize::deserialize(buf)?))),
+            3 => Ok(TriggerExpr::Leaf(BorshDeserialize::deserialize(buf)?)),
+            variant => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unexpected TriggerExpr variant {}", variant),
+            )),
+        }
+    }
+}
+
+/// JSON representation of `TriggerExpr`
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde", untagged, bound = "T: Serialize + DeserializeOwned + Clone")]
+enum TriggerExprRepr<T> {
+    All(Vec<TriggerExpr<T>>),
+    Any { any: Vec<TriggerExpr<T>> },
+    Not { not: Box<TriggerExpr<T>> },
+    Leaf(T),
+}
+
+impl<T> From<TriggerExprRepr<T>> for TriggerExpr<T> {
+    fn from(repr: TriggerExprRepr<T>) -> Self {
+        match repr {
+            TriggerExprRepr::All(exprs) => TriggerExpr::All(exprs),
+            TriggerExprRepr::Any { any } => TriggerExpr::Any(any),
+            TriggerExprRepr::Not { not } => TriggerExpr::Not(not),
+            TriggerExprRepr::Leaf(trigger) => TriggerExpr::Leaf(trigger),
+        }
+    }
+}
+
+impl<T> From<TriggerExpr<T>> for TriggerExprRepr<T> {
+    fn from(expr: TriggerExpr<T>) -> Self {
+        match expr {
+            TriggerExpr::All(exprs) => TriggerExprRepr::All(exprs),
+            TriggerExpr::Any(any) => TriggerExprRepr::Any { any },
+            TriggerExpr::Not(not) => TriggerExprRepr::Not { not },
+            TriggerExpr::Leaf(trigger) => TriggerExprRepr::Leaf(trigger),
+        }
+    }
+}
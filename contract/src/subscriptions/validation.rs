@@ -0,0 +1,556 @@
+//! Validation of subscriptions. Invalid subscriptions are rejected with a panic message
+//! `"<code>: <description>"` where the code is one of:
+//!
+//! | Code  | Error                        | Rule                                                              |
+//! |-------|------------------------------|-------------------------------------------------------------------|
+//! | `E01` | `EmptyTriggerList`           | `All` / `Any` (including a flat list of triggers) can't be empty   |
+//! | `E02` | `TriggerExprTooDeep`         | Expression depth is limited by `MAX_TRIGGER_EXPR_DEPTH`            |
+//! | `E03` | `TriggerExprTooLarge`        | Expression size is limited by `MAX_TRIGGER_EXPR_NODES`             |
+//! | `E04` | `DuplicateTrigger`           | `All` / `Any` can't have the same expression twice                 |
+//! | `E05` | `ImpossibleAmountRange`      | `...EqualOrGreater` can't exceed `...EqualOrLower` of the same `All` |
+//! | `E06` | `MissingAccountId`           | `AccountBalance` and `ContractDataChange` need an `AccountId` of the root `All` |
+//! | `E07` | `InvalidJsonPath`            | JSON paths must follow the `parse_json_path` syntax                |
+//! | `E08` | `InvalidFunctionNamePattern` | `FunctionNameLike` must follow the `glob_matches` syntax           |
+//! | `E09` | `InvalidAccountSuffix`       | Account suffix must be `*.` followed by a valid account id         |
+//! | `E10` | `EmptyAccountList`           | `...IdIn` account list can't be empty                              |
+//! | `E11` | `AccountListTooLong`         | `...IdIn` account list is limited by `MAX_ACCOUNT_LIST_LEN`        |
+//! | `E12` | `InvalidKeyPrefix`           | Storage key prefix must be from 1 to `MAX_DATA_KEY_PREFIX_LEN` bytes |
+//! | `E13` | `InvalidLogSubstring`        | Log substring must be from 1 to `MAX_LOG_SUBSTRING_LEN` characters |
+//! | `E14` | `TooManyConfirmations`       | `min_confirmations` is limited by `MAX_MIN_CONFIRMATIONS`          |
+//! | `E15` | `EndpointTooLong`            | Endpoint is limited by `MAX_ENDPOINT_LEN`                          |
+//! | `E16` | `InvalidEndpoint`            | Endpoint must be a valid URL                                       |
+//! | `E17` | `InsecureEndpoint`           | Endpoint must be an `https://` URL, see `normalize_endpoint`       |
+//! | `E18` | `EndpointWithCredentials`    | Endpoint can't have `user:password@` credentials                   |
+//! | `E19` | `PrivateEndpointHost`        | Endpoint host can't be a private, loopback or link-local address   |
+//! | `E20` | `InvalidEncryptedEndpoint`   | Encrypted endpoint must have a 24 bytes nonce and a bounded size   |
+//! | `E21` | `StaleDispatcherKey`         | Encrypted endpoint must be sealed for the current dispatcher key   |
+//! | `E22` | `InvalidSecretHash`          | Secret hash must be a sha256 hash                                  |
+//! | `E23` | `EmptyDelivery`              | Subscription must have a delivery target                           |
+//! | `E24` | `TooManyDeliveryTargets`     | Delivery targets are limited by `MAX_DELIVERY_TARGETS`             |
+//! | `E25` | `DuplicateDeliveryTarget`    | Delivery targets can't have duplicates                             |
+//! | `E26` | `InvalidTelegramChatId`      | Telegram chat id can't be `0`                                      |
+//! | `E27` | `InvalidEmailAddressHash`    | Email address hash must be a sha256 hash                           |
+//! | `E28` | `InvalidWebsocketChannel`    | Websocket channel must be of `[A-Za-z0-9_.:-]` and bounded size    |
+//! | `E29` | `InvalidContractCall`        | Contract call must have a valid method name and another receiver   |
+//! | `E30` | `ExpirationInPast`           | `expires_at` must be in the future                                 |
+//! | `E31` | `InvalidMaxDeliveries`       | `max_deliveries` must be positive                                  |
+//! | `E32` | `FunctionNameTooLong`        | `FunctionNameExact` is limited by `MAX_METHOD_NAME_LEN`            |
+//! | `E33` | `Nep297FieldTooLong`         | `Nep297Standard` / `Nep297Event` are limited by `MAX_NEP297_FIELD_LEN` |
+//! | `E34` | `TokenIdTooLong`             | NFT `TokenId` is limited by `MAX_TOKEN_ID_LEN`                     |
+//! | `E35` | `ErrorKindTooLong`           | `FailureWithErrorKind` is limited by `MAX_ERROR_KIND_LEN`          |
+use std::fmt;
+
+use crate::*;
+
+/// Length of `UserSubscription::secret_hash`
+pub const SECRET_HASH_LEN: usize = 32;
+
+/// Reason the subscription is rejected
+#[derive(Clone, PartialEq, Debug)]
+pub enum SubscriptionError {
+    EmptyTriggerList,
+    TriggerExprTooDeep,
+    TriggerExprTooLarge,
+    DuplicateTrigger,
+    ImpossibleAmountRange,
+    MissingAccountId(&'static str),
+    InvalidJsonPath(String),
+    InvalidFunctionNamePattern(String),
+    InvalidAccountSuffix(String),
+    EmptyAccountList,
+    AccountListTooLong,
+    InvalidKeyPrefix,
+    InvalidLogSubstring,
+    TooManyConfirmations,
+    EndpointTooLong,
+    InvalidEndpoint,
+    InsecureEndpoint,
+    EndpointWithCredentials,
+    PrivateEndpointHost,
+    InvalidEncryptedEndpoint,
+    StaleDispatcherKey,
+    InvalidSecretHash,
+    EmptyDelivery,
+    TooManyDeliveryTargets,
+    DuplicateDeliveryTarget,
+    InvalidTelegramChatId,
+    InvalidEmailAddressHash,
+    InvalidWebsocketChannel,
+    InvalidContractCall,
+    ExpirationInPast,
+    InvalidMaxDeliveries,
+    FunctionNameTooLong,
+    Nep297FieldTooLong,
+    TokenIdTooLong,
+    ErrorKindTooLong,
+}
+
+impl SubscriptionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SubscriptionError::EmptyTriggerList => "E01",
+            SubscriptionError::TriggerExprTooDeep => "E02",
+            SubscriptionError::TriggerExprTooLarge => "E03",
+            SubscriptionError::DuplicateTrigger => "E04",
+            SubscriptionError::ImpossibleAmountRange => "E05",
+            SubscriptionError::MissingAccountId(_) => "E06",
+            SubscriptionError::InvalidJsonPath(_) => "E07",
+            SubscriptionError::InvalidFunctionNamePattern(_) => "E08",
+            SubscriptionError::InvalidAccountSuffix(_) => "E09",
+            SubscriptionError::EmptyAccountList => "E10",
+            SubscriptionError::AccountListTooLong => "E11",
+            SubscriptionError::InvalidKeyPrefix => "E12",
+            SubscriptionError::InvalidLogSubstring => "E13",
+            SubscriptionError::TooManyConfirmations => "E14",
+            SubscriptionError::EndpointTooLong => "E15",
+            SubscriptionError::InvalidEndpoint => "E16",
+            SubscriptionError::InsecureEndpoint => "E17",
+            SubscriptionError::EndpointWithCredentials => "E18",
+            SubscriptionError::PrivateEndpointHost => "E19",
+            SubscriptionError::InvalidEncryptedEndpoint => "E20",
+            SubscriptionError::StaleDispatcherKey => "E21",
+            SubscriptionError::InvalidSecretHash => "E22",
+            SubscriptionError::EmptyDelivery => "E23",
+            SubscriptionError::TooManyDeliveryTargets => "E24",
+            SubscriptionError::DuplicateDeliveryTarget => "E25",
+            SubscriptionError::InvalidTelegramChatId => "E26",
+            SubscriptionError::InvalidEmailAddressHash => "E27",
+            SubscriptionError::InvalidWebsocketChannel => "E28",
+            SubscriptionError::InvalidContractCall => "E29",
+            SubscriptionError::ExpirationInPast => "E30",
+            SubscriptionError::InvalidMaxDeliveries => "E31",
+            SubscriptionError::FunctionNameTooLong => "E32",
+            SubscriptionError::Nep297FieldTooLong => "E33",
+            SubscriptionError::TokenIdTooLong => "E34",
+            SubscriptionError::ErrorKindTooLong => "E35",
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            SubscriptionError::EmptyTriggerList => write!(f, "Trigger list can't be empty"),
+            SubscriptionError::TriggerExprTooDeep => write!(
+                f,
+                "Trigger expression can't be nested deeper than {} levels",
+                MAX_TRIGGER_EXPR_DEPTH,
+            ),
+            SubscriptionError::TriggerExprTooLarge => write!(
+                f,
+                "Trigger expression can't have more than {} nodes",
+                MAX_TRIGGER_EXPR_NODES,
+            ),
+            SubscriptionError::DuplicateTrigger => write!(f, "Trigger list can't have duplicates"),
+            SubscriptionError::ImpossibleAmountRange => {
+                write!(f, "Lower amount bound can't exceed the upper amount bound")
+            }
+            SubscriptionError::MissingAccountId(kind) => {
+                write!(f, "{} event must have an AccountId trigger the other triggers are combined with by All", kind)
+            }
+            SubscriptionError::InvalidJsonPath(err) => write!(f, "{}", err),
+            SubscriptionError::InvalidFunctionNamePattern(err) => write!(f, "{}", err),
+            SubscriptionError::InvalidAccountSuffix(suffix) => write!(
+                f,
+                "Account suffix must be `*.` followed by a valid account id, got `{}`",
+                suffix,
+            ),
+            SubscriptionError::EmptyAccountList => write!(f, "Account list can't be empty"),
+            SubscriptionError::AccountListTooLong => write!(
+                f,
+                "Account list can't have more than {} accounts",
+                MAX_ACCOUNT_LIST_LEN,
+            ),
+            SubscriptionError::InvalidKeyPrefix => write!(
+                f,
+                "Key prefix must be from 1 to {} bytes long",
+                MAX_DATA_KEY_PREFIX_LEN,
+            ),
+            SubscriptionError::InvalidLogSubstring => write!(
+                f,
+                "Log substring must be from 1 to {} characters long",
+                MAX_LOG_SUBSTRING_LEN,
+            ),
+            SubscriptionError::TooManyConfirmations => write!(
+                f,
+                "Minimal confirmations can't exceed {}",
+                MAX_MIN_CONFIRMATIONS,
+            ),
+            SubscriptionError::EndpointTooLong => write!(
+                f,
+                "Endpoint can't be longer than {} characters",
+                MAX_ENDPOINT_LEN,
+            ),
+            SubscriptionError::InvalidEndpoint => write!(f, "Endpoint must be a valid URL"),
+            SubscriptionError::InsecureEndpoint => write!(f, "Endpoint must be an https:// URL"),
+            SubscriptionError::EndpointWithCredentials => write!(f, "Endpoint can't have credentials"),
+            SubscriptionError::PrivateEndpointHost => {
+                write!(f, "Endpoint host can't be a private, loopback or link-local address")
+            }
+            SubscriptionError::InvalidEncryptedEndpoint => write!(
+                f,
+                "Encrypted endpoint must have a {} bytes nonce and from 1 to {} bytes of ciphertext",
+                ENCRYPTED_ENDPOINT_NONCE_LEN,
+                MAX_ENCRYPTED_ENDPOINT_LEN,
+            ),
+            SubscriptionError::StaleDispatcherKey => {
+                write!(f, "Encrypted endpoint must be sealed for the current dispatcher key")
+            }
+            SubscriptionError::InvalidSecretHash => {
+                write!(f, "Secret hash must be a {} bytes sha256 hash", SECRET_HASH_LEN)
+            }
+            SubscriptionError::EmptyDelivery => write!(f, "Subscription must have at least one delivery target"),
+            SubscriptionError::TooManyDeliveryTargets => write!(
+                f,
+                "Subscription can't have more than {} delivery targets",
+                MAX_DELIVERY_TARGETS,
+            ),
+            SubscriptionError::DuplicateDeliveryTarget => write!(f, "Delivery targets can't have duplicates"),
+            SubscriptionError::InvalidTelegramChatId => write!(f, "Telegram chat id can't be 0"),
+            SubscriptionError::InvalidEmailAddressHash => write!(
+                f,
+                "Email address hash must be a {} bytes sha256 hash",
+                EMAIL_ADDRESS_HASH_LEN,
+            ),
+            SubscriptionError::InvalidWebsocketChannel => write!(
+                f,
+                "Websocket channel must be from 1 to {} characters of `[A-Za-z0-9_.:-]`",
+                MAX_WEBSOCKET_CHANNEL_LEN,
+            ),
+            SubscriptionError::InvalidContractCall => write!(
+                f,
+                "Contract call must have a valid method name and can't call this contract",
+            ),
+            SubscriptionError::ExpirationInPast => write!(f, "Subscription expiration must be in the future"),
+            SubscriptionError::InvalidMaxDeliveries => write!(f, "Maximal deliveries must be positive"),
+            SubscriptionError::FunctionNameTooLong => write!(
+                f,
+                "Function name can't be longer than {} characters",
+                MAX_METHOD_NAME_LEN,
+            ),
+            SubscriptionError::Nep297FieldTooLong => write!(
+                f,
+                "NEP-297 standard and event can't be longer than {} characters",
+                MAX_NEP297_FIELD_LEN,
+            ),
+            SubscriptionError::TokenIdTooLong => write!(
+                f,
+                "Token id can't be longer than {} characters",
+                MAX_TOKEN_ID_LEN,
+            ),
+            SubscriptionError::ErrorKindTooLong => write!(
+                f,
+                "Error kind can't be longer than {} characters",
+                MAX_ERROR_KIND_LEN,
+            ),
+        }
+    }
+}
+
+/// Panic with the error message unless the result is `Ok`
+pub fn assert_valid<T>(result: Result<T, SubscriptionError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+/// Bound of the amount a trigger sets
+pub enum AmountBound {
+    EqualOrGreater(Balance),
+    EqualOrLower(Balance),
+}
+
+/// Condition of an event the user is notified about
+pub trait Trigger: PartialEq {
+    /// Check the trigger can be checked by the dispatcher
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        Ok(())
+    }
+
+    /// Bound of the amount if the trigger limits it
+    fn amount_bound(&self) -> Option<AmountBound> {
+        None
+    }
+}
+
+impl<T: Trigger> TriggerExpr<T> {
+    pub fn validate(&self) -> Result<(), SubscriptionError> {
+        if self.depth() > MAX_TRIGGER_EXPR_DEPTH {
+            return Err(SubscriptionError::TriggerExprTooDeep);
+        }
+        if self.node_count() > MAX_TRIGGER_EXPR_NODES {
+            return Err(SubscriptionError::TriggerExprTooLarge);
+        }
+        self.validate_node()
+    }
+
+    fn validate_node(&self) -> Result<(), SubscriptionError> {
+        match self {
+            TriggerExpr::All(exprs) | TriggerExpr::Any(exprs) => {
+                if exprs.is_empty() {
+                    return Err(SubscriptionError::EmptyTriggerList);
+                }
+                for (index, expr) in exprs.iter().enumerate() {
+                    if exprs[..index].contains(expr) {
+                        return Err(SubscriptionError::DuplicateTrigger);
+                    }
+                    expr.validate_node()?;
+                }
+                if let TriggerExpr::All(exprs) = self {
+                    validate_amount_range(exprs)?;
+                }
+                Ok(())
+            }
+            TriggerExpr::Not(expr) => expr.validate_node(),
+            TriggerExpr::Leaf(trigger) => trigger.validate(),
+        }
+    }
+}
+
+/// Triggers of the same `All` must not set an empty amount range
+fn validate_amount_range<T: Trigger>(exprs: &[TriggerExpr<T>]) -> Result<(), SubscriptionError> {
+    let (mut lower, mut upper) = (None, None);
+    for expr in exprs {
+        if let TriggerExpr::Leaf(trigger) = expr {
+            match trigger.amount_bound() {
+                Some(AmountBound::EqualOrGreater(amount)) => lower = std::cmp::max(lower, Some(amount)),
+                Some(AmountBound::EqualOrLower(amount)) => {
+                    upper = Some(upper.map_or(amount, |upper: Balance| std::cmp::min(upper, amount)))
+                }
+                None => {}
+            }
+        }
+    }
+    match (lower, upper) {
+        (Some(lower), Some(upper)) if lower > upper => Err(SubscriptionError::ImpossibleAmountRange),
+        _ => Ok(()),
+    }
+}
+
+impl Event {
+    pub fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            Event::ReceiptTransferResult(expr) => expr.validate(),
+            Event::ReceiptFunctionCallResult(expr) => expr.validate(),
+            Event::ReceiptCreateAccountResult(expr) => expr.validate(),
+            Event::ReceiptDeployContractResult(expr) => expr.validate(),
+            Event::ReceiptStakeResult(expr) => expr.validate(),
+            Event::ReceiptAddKeyResult(expr) => expr.validate(),
+            Event::ReceiptDeleteKeyResult(expr) => expr.validate(),
+            Event::ReceiptDeleteAccountResult(expr) => expr.validate(),
+            Event::FtTransfer(expr) => expr.validate(),
+            Event::NftTransfer(expr) => expr.validate(),
+            Event::AccountBalance(expr) => {
+                expr.validate()?;
+                if !expr.conjuncts().iter().any(|trigger| matches!(trigger, BalanceTrigger::AccountId(_))) {
+                    return Err(SubscriptionError::MissingAccountId("AccountBalance"));
+                }
+                Ok(())
+            }
+            Event::ContractDataChange(expr) => {
+                expr.validate()?;
+                if !expr.conjuncts().iter().any(|trigger| matches!(trigger, DataChangeTrigger::AccountId(_))) {
+                    return Err(SubscriptionError::MissingAccountId("ContractDataChange"));
+                }
+                Ok(())
+            }
+            Event::ContractLog(expr) => expr.validate(),
+            Event::TransactionOutcome(expr) => expr.validate(),
+            Event::WatchTransaction(_) | Event::WatchReceipt(_) => Ok(()),
+        }
+    }
+
+    pub fn assert_valid(&self) {
+        assert_valid(self.validate());
+    }
+}
+
+/// Check the commitment to the webhook signing secret is a sha256 hash.
+/// Receivers compare `sha256(secret)` of their secret with the on-chain hash to trust the signed notifications
+pub fn validate_secret_hash(secret_hash: &Base64VecU8) -> Result<(), SubscriptionError> {
+    if secret_hash.0.len() != SECRET_HASH_LEN {
+        return Err(SubscriptionError::InvalidSecretHash);
+    }
+    Ok(())
+}
+
+/// Check the expiration limits of the subscription, `None` means no limit
+pub fn validate_expiration(expires_at: Option<U64>, max_deliveries: Option<u64>) -> Result<(), SubscriptionError> {
+    if matches!(expires_at, Some(expires_at) if expires_at.0 <= env::block_timestamp()) {
+        return Err(SubscriptionError::ExpirationInPast);
+    }
+    if max_deliveries == Some(0) {
+        return Err(SubscriptionError::InvalidMaxDeliveries);
+    }
+    Ok(())
+}
+
+/// Normalize the confirmations delay, `0` means no delay
+pub fn validate_min_confirmations(min_confirmations: Option<u32>) -> Result<Option<u32>, SubscriptionError> {
+    match min_confirmations {
+        Some(0) | None => Ok(None),
+        Some(confirmations) if confirmations > MAX_MIN_CONFIRMATIONS => Err(SubscriptionError::TooManyConfirmations),
+        Some(confirmations) => Ok(Some(confirmations)),
+    }
+}
+
+fn validate_account_suffix(suffix: &str) -> Result<(), SubscriptionError> {
+    let parent = suffix.strip_prefix("*.").unwrap_or("");
+    if !env::is_valid_account_id(parent.as_bytes()) {
+        return Err(SubscriptionError::InvalidAccountSuffix(suffix.to_string()));
+    }
+    Ok(())
+}
+
+fn validate_account_list(account_ids: &[ValidAccountId]) -> Result<(), SubscriptionError> {
+    if account_ids.is_empty() {
+        return Err(SubscriptionError::EmptyAccountList);
+    }
+    if account_ids.len() > MAX_ACCOUNT_LIST_LEN {
+        return Err(SubscriptionError::AccountListTooLong);
+    }
+    Ok(())
+}
+
+fn validate_json_path(path: &str) -> Result<(), SubscriptionError> {
+    parse_json_path(path).map(|_| ()).map_err(SubscriptionError::InvalidJsonPath)
+}
+
+fn validate_outcome_status(status: &OutcomeStatus) -> Result<(), SubscriptionError> {
+    match status {
+        OutcomeStatus::FailureWithErrorKind(error_kind) if error_kind.len() > MAX_ERROR_KIND_LEN => {
+            Err(SubscriptionError::ErrorKindTooLong)
+        }
+        _ => Ok(()),
+    }
+}
+
+impl Trigger for TransferTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            TransferTrigger::ReceiverIdSuffix(suffix) => validate_account_suffix(suffix),
+            TransferTrigger::ReceiverIdIn(account_ids) | TransferTrigger::SignerIdIn(account_ids) => {
+                validate_account_list(account_ids)
+            }
+            TransferTrigger::OutcomeStatus(status) => validate_outcome_status(status),
+            _ => Ok(()),
+        }
+    }
+
+    fn amount_bound(&self) -> Option<AmountBound> {
+        match self {
+            TransferTrigger::AmountEqualOrGreater(amount) => Some(AmountBound::EqualOrGreater(amount.0)),
+            TransferTrigger::AmountEqualOrLower(amount) => Some(AmountBound::EqualOrLower(amount.0)),
+            _ => None,
+        }
+    }
+}
+
+impl Trigger for FunctionCallTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            FunctionCallTrigger::ArgsJsonPath { path, .. } | FunctionCallTrigger::ArgsJsonPathExists(path) => {
+                validate_json_path(path)
+            }
+            FunctionCallTrigger::FunctionNameExact(name) if name.len() > MAX_METHOD_NAME_LEN => {
+                Err(SubscriptionError::FunctionNameTooLong)
+            }
+            FunctionCallTrigger::FunctionNameLike(pattern) => {
+                validate_glob(pattern).map_err(SubscriptionError::InvalidFunctionNamePattern)
+            }
+            FunctionCallTrigger::OutcomeStatus(status) => validate_outcome_status(status),
+            FunctionCallTrigger::ReceiverIdSuffix(suffix) => validate_account_suffix(suffix),
+            FunctionCallTrigger::ReceiverIdIn(account_ids) | FunctionCallTrigger::SignerIdIn(account_ids) => {
+                validate_account_list(account_ids)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn amount_bound(&self) -> Option<AmountBound> {
+        match self {
+            FunctionCallTrigger::AttachedDepositEqualOrGreater(amount) => Some(AmountBound::EqualOrGreater(amount.0)),
+            FunctionCallTrigger::AttachedDepositEqualOrLower(amount) => Some(AmountBound::EqualOrLower(amount.0)),
+            _ => None,
+        }
+    }
+}
+
+impl Trigger for FtTransferTrigger {
+    fn amount_bound(&self) -> Option<AmountBound> {
+        match self {
+            FtTransferTrigger::AmountEqualOrGreater(amount) => Some(AmountBound::EqualOrGreater(amount.0)),
+            FtTransferTrigger::AmountEqualOrLower(amount) => Some(AmountBound::EqualOrLower(amount.0)),
+            _ => None,
+        }
+    }
+}
+
+impl Trigger for StakeTrigger {
+    fn amount_bound(&self) -> Option<AmountBound> {
+        match self {
+            StakeTrigger::AmountEqualOrGreater(amount) => Some(AmountBound::EqualOrGreater(amount.0)),
+            StakeTrigger::AmountEqualOrLower(amount) => Some(AmountBound::EqualOrLower(amount.0)),
+            _ => None,
+        }
+    }
+}
+
+impl Trigger for DataChangeTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            DataChangeTrigger::KeyPrefixBase64(prefix)
+                if prefix.0.is_empty() || prefix.0.len() > MAX_DATA_KEY_PREFIX_LEN =>
+            {
+                Err(SubscriptionError::InvalidKeyPrefix)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Trigger for LogTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            LogTrigger::LogContains(substring)
+                if substring.is_empty() || substring.len() > MAX_LOG_SUBSTRING_LEN =>
+            {
+                Err(SubscriptionError::InvalidLogSubstring)
+            }
+            LogTrigger::Nep297Standard(field) | LogTrigger::Nep297Event(field) if field.len() > MAX_NEP297_FIELD_LEN => {
+                Err(SubscriptionError::Nep297FieldTooLong)
+            }
+            LogTrigger::Nep297DataJsonPath { path, .. } => validate_json_path(path),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Trigger for NftTransferTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            NftTransferTrigger::TokenId(token_id) if token_id.len() > MAX_TOKEN_ID_LEN => {
+                Err(SubscriptionError::TokenIdTooLong)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Trigger for TransactionTrigger {
+    fn validate(&self) -> Result<(), SubscriptionError> {
+        match self {
+            TransactionTrigger::OutcomeStatus(status) => validate_outcome_status(status),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Trigger for BalanceTrigger {}
+impl Trigger for CreateAccountTrigger {}
+impl Trigger for DeployContractTrigger {}
+impl Trigger for AddKeyTrigger {}
+impl Trigger for DeleteKeyTrigger {}
+impl Trigger for DeleteAccountTrigger {}
@@ -8,11 +8,77 @@ impl Contract {
         self.exchange_price_in_yocto_near
     }
 
+    /// Active subscriptions of the account, the expired ones are left out until they're pruned
     pub fn subscriptions_of(&self, account_id: AccountId) -> Vec<subscriptions::UserSubscription> {
-        if let Some(subscriptions) = self.subscriptions.get(&account_id) {
+        if let Some(subscriptions) = self.internal_subscriptions_of(&account_id) {
+            let now = env::block_timestamp();
             // TODO: add pagination
-            return subscriptions
+            return subscriptions.into_iter().filter(|subscription| !subscription.is_expired(now)).collect()
         }
         vec![]
     }
+
+    /// Show the referral program settings
+    pub fn referral_config(&self) -> ReferralConfig {
+        self.referral_config.clone()
+    }
+
+    /// Show the referral statistics of the account
+    pub fn referral_stats(&self, account_id: ValidAccountId) -> ReferralStats {
+        self.referral_stats.get(account_id.as_ref()).unwrap_or_default()
+    }
+
+    /// Show the promo code by its sha256 hash
+    pub fn promo_code(&self, code_hash: Base64VecU8) -> Option<PromoCode> {
+        self.promo_codes.get(&code_hash.0)
+    }
+
+    /// Show the regular balance and the promo credits of the account
+    pub fn credits_of(&self, account_id: ValidAccountId) -> Credits {
+        let promo_balance = self.internal_promo_balance(account_id.as_ref());
+        let promo_expires_at = if promo_balance > 0 {
+            self.promo_credits.get(account_id.as_ref()).map(|promo_credit| promo_credit.expires_at)
+        } else {
+            None
+        };
+        Credits {
+            balance: self.token.accounts.get(account_id.as_ref()).unwrap_or(0).into(),
+            promo_balance: promo_balance.into(),
+            promo_expires_at,
+        }
+    }
+
+    /// Show the airdrop by its id
+    pub fn airdrop(&self, airdrop_id: u64) -> Option<Airdrop> {
+        self.airdrops.get(airdrop_id)
+    }
+
+    /// Check if the airdrop leaf with the given index has been claimed
+    pub fn is_airdrop_claimed(&self, airdrop_id: u64, index: u64) -> bool {
+        let word = self.airdrop_claims.get(&(airdrop_id, index / CLAIMED_BITMAP_WORD_BITS)).unwrap_or(0);
+        word & (1u128 << (index % CLAIMED_BITMAP_WORD_BITS)) != 0
+    }
+
+    /// Check if `http://localhost` subscription endpoints are allowed
+    pub fn localhost_endpoints_allowed(&self) -> bool {
+        self.allow_localhost_endpoints
+    }
+
+    /// Show the dispatcher key of the given version, the latest one by default
+    pub fn dispatcher_key(&self, version: Option<u64>) -> Option<DispatcherKey> {
+        match version {
+            Some(version) => self.dispatcher_keys.get(version),
+            None => self.dispatcher_keys.len().checked_sub(1).and_then(|version| self.dispatcher_keys.get(version)),
+        }
+    }
+
+    /// Show the gas budget of the relayed contract calls of the account
+    pub fn gas_budget_of(&self, account_id: ValidAccountId) -> GasBudget {
+        self.gas_budgets.get(account_id.as_ref()).unwrap_or_default()
+    }
+
+    /// Show the outcomes of the relayed contract calls of the subscription
+    pub fn delivery_results(&self, account_id: ValidAccountId, subscription_id: SubscriptionId) -> Option<DeliveryResults> {
+        self.delivery_results.get(&(account_id.into(), subscription_id))
+    }
 }
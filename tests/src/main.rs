@@ -1,10 +1,22 @@
 use serde_json::json;
 use workspaces::prelude::*;
 
-const CONTRACT_FILE: &str = "../contract/res/thestreamer_contract.wasm";
+const CONTRACT_DIR: &str = "../contract";
+const CONTRACT_FILE: &str = "../contract/target/wasm32-unknown-unknown/release/thestreamer_contract.wasm";
+
+/// Build the production wasm from the current sources, so the test never runs against a stale artifact
+fn build_contract() -> anyhow::Result<()> {
+    let status = std::process::Command::new("cargo")
+        .args(&["build", "--target", "wasm32-unknown-unknown", "--release"])
+        .current_dir(CONTRACT_DIR)
+        .status()?;
+    anyhow::ensure!(status.success(), "Contract build has failed");
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    build_contract()?;
     let worker = workspaces::sandbox();
     let wasm = std::fs::read(CONTRACT_FILE)?;
     let contract = worker.dev_deploy(wasm).await.unwrap();
@@ -41,7 +53,7 @@ async fn main() -> anyhow::Result<()> {
             &user.signer(),
             &contract,
             "buy_ft_tokens".to_string(),
-            "".to_string().into_bytes(),
+            json!({}).to_string().into_bytes(),
             Some(10_000000000000000000000000),
         ).await?;
     println!("User buys ft_tokens of TheStreamer and registers");
@@ -66,10 +78,27 @@ async fn main() -> anyhow::Result<()> {
 
     assert!(&result == "10");
 
+    println!("Owner allows http://localhost endpoints for the local dispatcher");
+
+    let outcome = worker
+        .call(
+            &contract.signer(),
+            &contract,
+            "set_localhost_endpoints_allowed".to_string(),
+            json!({"allowed": true}).to_string().into_bytes(),
+            None,
+        ).await?;
+    println!("set_localhost_endpoints_allowed outcome: {:#?}", outcome);
+
     println!("User creates a subscription for all transfers where they are a receiver");
 
     let subscription_args = json!({
-        "endpoint": "http://localhost:3030",
+        "delivery": json!([
+            json!({
+                "type": "Webhook",
+                "url": "http://localhost:3030",
+            })
+        ]),
         "event": json!({
             "kind": "ReceiptTransferResult",
             "triggers": json!([
@@ -87,7 +116,7 @@ async fn main() -> anyhow::Result<()> {
             &contract,
             "create_subscription".to_string(),
             subscription_args.to_string().into_bytes(),
-            None,
+            Some(10_000000000000000000000),
         ).await?;
     println!("create_subscription outcome: {:#?}", outcome);
 